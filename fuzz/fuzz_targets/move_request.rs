@@ -0,0 +1,45 @@
+#![no_main]
+
+use battlesnake_game_of_chicken_lib::{
+    OriginalGameState,
+    logic::{
+        VARIANTS,
+        general::{field::BasicField, game_state::GameState},
+        get_move,
+    },
+    validate_game_state,
+};
+use libfuzzer_sys::fuzz_target;
+use std::env;
+
+// Requests passing the validation must be handled by every variant without panicking.
+// Set VARIANT to fuzz a single variant, the slower ones take up to 200ms per move.
+fuzz_target!(|data: &[u8]| {
+    let Ok(state) = serde_json::from_slice::<OriginalGameState>(data) else {
+        return;
+    };
+    if validate_game_state(&state).is_err() {
+        return;
+    }
+
+    unsafe {
+        env::set_var("MODE", "test");
+        env::set_var("SIMULATION_TIME_MS", "5");
+    }
+
+    let gamestate = GameState::<BasicField>::from(&state);
+    let _ = gamestate.valid_moves();
+    let mut next = gamestate.clone();
+    next.next_state([None; 4]);
+
+    match env::var("VARIANT") {
+        Ok(variant) => {
+            get_move(&state, variant);
+        }
+        Err(_) => {
+            for variant in VARIANTS {
+                get_move(&state, variant.to_string());
+            }
+        }
+    }
+});
@@ -1,112 +1,118 @@
-use battlesnake_game_of_chicken_lib::{OriginalDirection, OriginalGameState, logic};
+use battlesnake_game_of_chicken_lib::{OriginalDirection, OriginalGameState};
+use battlesnake_game_of_chicken_lib::game_log::{Game, parse_log};
+use battlesnake_game_of_chicken_lib::logic::config::{self, Params};
+use battlesnake_game_of_chicken_lib::logic::general::coord::Coord;
+use battlesnake_game_of_chicken_lib::logic::general::direction::{DIRECTIONS, Direction};
 use battlesnake_game_of_chicken_lib::logic::general::field::BasicField;
 use battlesnake_game_of_chicken_lib::logic::general::game_state::GameState;
-use std::collections::HashMap;
+use battlesnake_game_of_chicken_lib::logic::general::moves::Moves;
+use battlesnake_game_of_chicken_lib::logic::general::snakes::{SNAKES, Snakes};
+use battlesnake_game_of_chicken_lib::logic::single_gamestate_nodes::GamestateNodesSnake;
 use std::env;
 use std::fs;
-use std::io::{BufRead, BufReader};
 use std::path::Path;
 
-struct TurnRecord {
-    turn: i32,
-    json: String,
-    picked: Option<OriginalDirection>,
-}
-
-struct Game {
-    id: String,
-    turns: Vec<TurnRecord>,
-}
+/// Time budget of the snake in production, used to explain why it picked differently
+const PRODUCTION_TIME_MS: u64 = 200;
 
-fn parse_result_line(line: &str) -> Option<(String, i32, OriginalDirection)> {
-    // "... ID <game_id> Turn <turn_num> Result -> <direction>"
-    let id_pos = line.find("] ID ")?;
-    let after_id = &line[id_pos + 5..];
-    let space = after_id.find(' ')?;
-    let game_id = after_id[..space].to_string();
-
-    let turn_pos = after_id.find("Turn ")?;
-    let after_turn = &after_id[turn_pos + 5..];
-    let arrow = after_turn.find(" Result -> ")?;
-    let turn: i32 = after_turn[..arrow].parse().ok()?;
-    let dir_str = after_turn[arrow + 11..].trim();
-
-    let direction = match dir_str {
-        "up" => OriginalDirection::Up,
-        "down" => OriginalDirection::Down,
-        "left" => OriginalDirection::Left,
-        "right" => OriginalDirection::Right,
-        _ => return None,
-    };
-
-    Some((game_id, turn, direction))
+fn direction_name(d: &OriginalDirection) -> &'static str {
+    match d {
+        OriginalDirection::Up => "up",
+        OriginalDirection::Down => "down",
+        OriginalDirection::Left => "left",
+        OriginalDirection::Right => "right",
+    }
 }
 
-fn parse_log(path: &str) -> Vec<Game> {
-    let file = fs::File::open(path).expect("Cannot open log file");
-    let reader = BufReader::new(file);
-    let lines: Vec<String> = reader.lines().map(|l| l.unwrap()).collect();
-
-    // Group turns by game ID
-    let mut game_turns: HashMap<String, Vec<TurnRecord>> = HashMap::new();
-    let mut game_order: Vec<String> = Vec::new();
-
-    for line in &lines {
-        if let Some((game_id, turn, json)) = parse_turn_line(line) {
-            if !game_turns.contains_key(&game_id) {
-                game_order.push(game_id.clone());
-            }
-            game_turns.entry(game_id).or_default().push(TurnRecord {
-                turn,
-                json,
-                picked: None,
-            });
-        } else if let Some((game_id, turn, direction)) = parse_result_line(line) {
-            if let Some(turns) = game_turns.get_mut(&game_id) {
-                if let Some(record) = turns.iter_mut().find(|r| r.turn == turn) {
-                    record.picked = Some(direction);
-                }
-            }
+// Moves of the opponents from `before` to `after`, in the snake slots of `root`
+fn observed_moves(
+    root: &OriginalGameState,
+    before: &OriginalGameState,
+    after: &OriginalGameState,
+) -> Moves {
+    let ids = Snakes::request_ids(&root.board, &root.you);
+    let mut moves: Moves = [None; SNAKES];
+    for slot in 1..SNAKES {
+        let Some(id) = ids[slot] else {
+            continue;
+        };
+        let head = |state: &OriginalGameState| {
+            state
+                .board
+                .snakes
+                .iter()
+                .find(|snake| snake.id == id)
+                .map(|snake| Coord::from(&snake.head))
+        };
+        if let (Some(from), Some(to)) = (head(before), head(after)) {
+            moves[slot] = Direction::try_from(to - from).ok();
         }
     }
+    moves
+}
 
-    game_order
-        .into_iter()
-        .map(|id| {
-            let mut turns = game_turns.remove(&id).unwrap();
-            turns.sort_by_key(|t| t.turn);
-            Game { id, turns }
-        })
+fn direction_names(directions: &[bool; 4]) -> Vec<&'static str> {
+    DIRECTIONS
+        .iter()
+        .filter(|&&direction| directions[direction as usize])
+        .map(|&direction| direction_name(&direction.into()))
         .collect()
 }
 
-fn parse_turn_line(line: &str) -> Option<(String, i32, String)> {
-    // "... ID <game_id> Turn <turn_num> Request -> <json>"
-    let id_pos = line.find("] ID ")?;
-    let after_id = &line[id_pos + 5..];
-    let space = after_id.find(' ')?;
-    let game_id = after_id[..space].to_string();
-
-    let turn_pos = after_id.find("Turn ")?;
-    let after_turn = &after_id[turn_pos + 5..];
-    let arrow = after_turn.find(" Request -> ")?;
-    let turn: i32 = after_turn[..arrow].parse().ok()?;
-    let json = after_turn[arrow + 12..].to_string();
+// Walks back from the death turn and returns the last turn at which a surviving line against the
+// observed opponent moves existed, together with all directions that survive from there.
+fn retrograde(game: &Game, max_turns_back: usize) -> Option<(usize, [bool; 4])> {
+    let states: Vec<Option<OriginalGameState>> = game
+        .turns
+        .iter()
+        .map(|record| serde_json::from_str(&record.json).ok())
+        .collect();
+    let last = states.len().checked_sub(1)?;
 
-    Some((game_id, turn, json))
-}
+    for index in (last.saturating_sub(max_turns_back.saturating_sub(1))..=last).rev() {
+        let Some(root) = &states[index] else {
+            break;
+        };
+        // Collect the observed opponent moves up to the death turn
+        let mut moves = Vec::new();
+        for k in index..last {
+            let (Some(before), Some(after)) = (&states[k], &states[k + 1]) else {
+                break;
+            };
+            if after.turn != before.turn + 1 {
+                break;
+            }
+            moves.push(observed_moves(root, before, after));
+        }
+        if moves.len() != last - index {
+            break;
+        }
 
-fn direction_name(d: &OriginalDirection) -> &'static str {
-    match d {
-        OriginalDirection::Up => "up",
-        OriginalDirection::Down => "down",
-        OriginalDirection::Left => "left",
-        OriginalDirection::Right => "right",
+        let depth = moves.len() + 1;
+        let safe = std::panic::catch_unwind(|| {
+            GamestateNodesSnake::surviving_directions(GameState::<BasicField>::from(root), moves)
+        });
+        let Ok(safe) = safe else {
+            eprintln!("    Turn {}: PANICKED (skipping)", game.turns[index].turn);
+            break;
+        };
+        eprintln!(
+            "    Turn {}: surviving {} turns with [{}]",
+            game.turns[index].turn,
+            depth,
+            direction_names(&safe).join(", ")
+        );
+        if safe.iter().any(|&x| x) {
+            return Some((index, safe));
+        }
     }
+    None
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    let retrograde_mode = args.iter().any(|arg| arg == "--retrograde");
+    args.retain(|arg| arg != "--retrograde");
     let log_dir = args.get(1).map(|s| s.as_str()).unwrap_or("game_logs");
     let timeout_ms: u64 = args
         .get(2)
@@ -117,6 +123,9 @@ fn main() {
         .and_then(|s| s.parse().ok())
         .unwrap_or(20);
 
+    if retrograde_mode {
+        eprintln!("Mode: retrograde");
+    }
     eprintln!("Re-evaluation timeout: {}ms", timeout_ms);
     eprintln!("Max turns back per game: {}", max_turns_back);
 
@@ -139,7 +148,7 @@ fn main() {
         .iter()
         .flat_map(|path| {
             eprintln!("  Parsing {}", path);
-            parse_log(path)
+            parse_log(path).unwrap_or_else(|e| panic!("{}", e))
         })
         .collect();
     let lost_games: Vec<&Game> = games.iter().collect();
@@ -168,11 +177,45 @@ fn main() {
     let mut saved_count = 0;
 
     for game in &lost_games {
-        eprintln!(
-            "\nGame {} ({} turns):",
-            &game.id[..8.min(game.id.len())],
-            game.turns.len()
-        );
+        // Game ids come from the logs, they are not necessarily ASCII
+        let short_id: String = game.id.chars().take(8).collect();
+        eprintln!("\nGame {} ({} turns):", short_id, game.turns.len());
+
+        if retrograde_mode {
+            let Some((index, safe)) = retrograde(game, max_turns_back) else {
+                eprintln!("    No surviving line found");
+                continue;
+            };
+            let turn_record = &game.turns[index];
+            let picked = turn_record.picked.as_ref().map_or("?", direction_name);
+            if let Some(picked) = turn_record.picked
+                && safe[Direction::from(picked) as usize]
+            {
+                eprintln!("    Picked {} was safe, not a blunder", direction_name(&picked));
+                continue;
+            }
+
+            let gs: OriginalGameState = serde_json::from_str(&turn_record.json).unwrap();
+            let board_state = GameState::<BasicField>::from(&gs);
+            let safe_names = direction_names(&safe);
+            let filename = format!(
+                "game_{}_turn_{}_safe_{}.json",
+                short_id,
+                turn_record.turn,
+                safe_names.join("_")
+            );
+            fs::write(out_dir.join(&filename), &turn_record.json).unwrap();
+            let txt_content = format!(
+                "Picked: {}  Safe: {}\n\n{}",
+                picked,
+                safe_names.join(", "),
+                board_state
+            );
+            fs::write(out_dir.join(filename.replace(".json", ".txt")), txt_content).unwrap();
+            saved_count += 1;
+            eprintln!("    BLUNDER: {} (saved: {})", picked, filename);
+            continue;
+        }
 
         // Iterate from last turn backwards
         let start = if game.turns.len() > max_turns_back {
@@ -205,15 +248,33 @@ fn main() {
             eprintln!("  Re-evaluating...");
 
             let re_eval = std::panic::catch_unwind(|| {
-                logic::get_move_with_evaluation(&gs)
+                let (evaluation, _) = GamestateNodesSnake::new().evaluate(&gs);
+                (evaluation.result().into(), evaluation.to_string(), evaluation.report())
             });
 
             match re_eval {
-                Ok((new_pick, eval_string)) => {
+                Ok((new_pick, eval_string, report)) => {
                     if new_pick != *original_pick {
+                        // Evaluate again with the production budget to show what the longer search changed
+                        let params =
+                            Params::from([("SIMULATION_TIME_MS".to_string(), PRODUCTION_TIME_MS.to_string())]);
+                        let production = std::panic::catch_unwind(|| {
+                            config::with_params(params, || {
+                                GamestateNodesSnake::new().evaluate(&gs).0.report()
+                            })
+                        });
+                        let diff = match production {
+                            Ok(production) => format!(
+                                "Changes from {}ms to {}ms\n{}",
+                                PRODUCTION_TIME_MS,
+                                timeout_ms,
+                                production.diff(&report)
+                            ),
+                            Err(_) => String::new(),
+                        };
                         let filename = format!(
                             "game_{}_turn_{}_{}.json",
-                            &game.id[..8.min(game.id.len())],
+                            short_id,
                             turn_record.turn,
                             direction_name(&new_pick)
                         );
@@ -222,7 +283,7 @@ fn main() {
 
                         let txt_filename = filename.replace(".json", ".txt");
                         let txt_path = out_dir.join(&txt_filename);
-                        let txt_content = format!("Picked: {}  Should have picked: {}\n\n{}\n{}", direction_name(original_pick), direction_name(&new_pick), board_state, eval_string);
+                        let txt_content = format!("Picked: {}  Should have picked: {}\n\n{}\n{}\n{}", direction_name(original_pick), direction_name(&new_pick), board_state, eval_string, diff);
                         fs::write(&txt_path, txt_content).unwrap();
 
                         saved_count += 1;
@@ -249,5 +310,9 @@ fn main() {
         env::remove_var("SIMULATION_TIME_MS");
     }
 
-    eprintln!("\nSaved {} differing game states to requests/automated/", saved_count);
+    if retrograde_mode {
+        eprintln!("\nSaved {} blunder game states to requests/automated/", saved_count);
+    } else {
+        eprintln!("\nSaved {} differing game states to requests/automated/", saved_count);
+    }
 }
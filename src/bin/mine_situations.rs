@@ -0,0 +1,94 @@
+use battlesnake_game_of_chicken_lib::{
+    logic::{
+        general::{field::BasicField, game_state::GameState},
+        single_gamestate_nodes::situation::mining::{Encoding, Miner, export},
+    },
+    read_game_state,
+    regression::Manifest,
+};
+use std::{env, fs, path::Path, process, str::FromStr};
+
+// Usage: mine_situations [--radius <n>] [--coarse] [--min-support <n>] [--min-accuracy <f>]
+//                        [--output <file>] [<dir> | <manifest.json>]...
+// Allowed moves are labelled good, forbidden moves and moves besides the allowed ones bad.
+// Without targets requests/manifest.json and requests/automated are mined if they exist.
+fn main() {
+    let mut radius = 2;
+    let mut encoding = Encoding::Fine;
+    let mut min_support = 2;
+    let mut min_accuracy = 0.8;
+    let mut output: Option<String> = None;
+    let mut targets: Vec<String> = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--radius" => radius = number(args.next(), "--radius"),
+            "--coarse" => encoding = Encoding::Coarse,
+            "--min-support" => min_support = number(args.next(), "--min-support"),
+            "--min-accuracy" => min_accuracy = number(args.next(), "--min-accuracy"),
+            "--output" => output = args.next(),
+            _ => targets.push(arg),
+        }
+    }
+    if targets.is_empty() {
+        targets = ["requests/manifest.json", "requests/automated"]
+            .into_iter()
+            .filter(|target| Path::new(target).exists())
+            .map(String::from)
+            .collect();
+    }
+
+    let mut miner = Miner::new(radius, encoding);
+    let mut states = 0;
+    for target in &targets {
+        let (dir, manifest) = Manifest::resolve(target).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        });
+        for entry in &manifest.tests {
+            let expectation = &entry.expectation;
+            if expectation.skip {
+                continue;
+            }
+            let (good, bad) = expectation.labels();
+            if good.is_empty() && bad.is_empty() {
+                continue;
+            }
+            let path = Path::new(&dir).join(&entry.file);
+            let gamestate = read_game_state(&path.to_string_lossy());
+            let state = GameState::<BasicField>::from(&gamestate);
+            miner.add(&entry.file, &state, &good, &bad);
+            states += 1;
+        }
+    }
+
+    let candidates = miner.candidates(min_support, min_accuracy);
+    eprintln!(
+        "Mined {} states into {} clusters, {} candidates:",
+        states,
+        miner.clusters().len(),
+        candidates.len()
+    );
+    for candidate in &candidates {
+        eprintln!("  {}", candidate);
+    }
+
+    let situations = export(&candidates);
+    match output {
+        Some(path) => {
+            if let Err(e) = fs::write(&path, situations) {
+                eprintln!("Cannot write {}: {}", path, e);
+                process::exit(1);
+            }
+            eprintln!("Wrote {}", path);
+        }
+        None => print!("{}", situations),
+    }
+}
+
+fn number<T: FromStr>(value: Option<String>, name: &str) -> T {
+    value.and_then(|v| v.parse().ok()).unwrap_or_else(|| {
+        eprintln!("{} needs a number", name);
+        process::exit(1);
+    })
+}
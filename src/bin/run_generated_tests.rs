@@ -1,105 +1,94 @@
-use battlesnake_game_of_chicken_lib::{OriginalDirection, read_game_state, logic};
+use battlesnake_game_of_chicken_lib::regression::{Manifest, load_evaluations};
 use std::env;
-use std::fs;
 use std::process;
 
-fn parse_expected_direction(filename: &str) -> Option<OriginalDirection> {
-    let stem = filename.strip_suffix(".json")?;
-    let dir_str = stem.rsplit('_').next()?;
-    match dir_str {
-        "up" => Some(OriginalDirection::Up),
-        "down" => Some(OriginalDirection::Down),
-        "left" => Some(OriginalDirection::Left),
-        "right" => Some(OriginalDirection::Right),
-        _ => None,
-    }
-}
-
+// Usage: run_generated_tests [--variant <name>]... [--tag <tag>] [--save-evaluations <file>]
+//                            [--baseline <file>] [<dir> | <manifest.json>]
+// A directory without manifest.json takes the allowed moves from the filenames.
+// Evaluations saved by an earlier run explain the moves that changed since as a baseline.
 fn main() {
-    let dir = env::args()
-        .nth(1)
-        .unwrap_or_else(|| "requests/automated".to_string());
+    let mut variants: Vec<String> = Vec::new();
+    let mut tag: Option<String> = None;
+    let mut save_evaluations: Option<String> = None;
+    let mut baseline: Option<String> = None;
+    let mut target = "requests/automated".to_string();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--variant" => variants.extend(args.next()),
+            "--tag" => tag = args.next(),
+            "--save-evaluations" => save_evaluations = args.next(),
+            "--baseline" => baseline = args.next(),
+            _ => target = arg,
+        }
+    }
 
-    let Ok(entries) = fs::read_dir(&dir) else {
-        eprintln!("No test directory found at {}", dir);
+    let (dir, manifest) = Manifest::resolve(&target).unwrap_or_else(|e| {
+        eprintln!("{}", e);
         process::exit(1);
-    };
+    });
+    let baseline = baseline.map(|path| {
+        load_evaluations(&path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        })
+    });
 
-    let mut files: Vec<String> = entries
-        .flatten()
-        .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
-        .map(|e| e.file_name().to_string_lossy().to_string())
-        .collect();
-    files.sort();
-
-    if files.is_empty() {
-        eprintln!("No test files found in {}", dir);
+    if manifest.tests.is_empty() {
+        eprintln!("No tests found in {}", target);
         process::exit(0);
     }
 
     unsafe {
-        env::set_var("MODE", "test");
         env::set_var("RUST_LOG", "info");
     }
     env_logger::builder()
         .format_timestamp(None)
         .format_target(false)
         .init();
+    // Panics are reported per test
+    std::panic::set_hook(Box::new(|_| {}));
 
-    eprintln!("Running {} generated tests from {}:", files.len(), dir);
+    eprintln!("Running {} tests from {}:", manifest.tests.len(), target);
 
-    let mut pass = 0;
-    let mut fail = 0;
-    let mut panicked = 0;
-    let mut failures: Vec<String> = Vec::new();
+    let report = manifest.run(&dir, &variants, tag.as_deref(), |result| {
+        eprintln!("  {} [{}] ... {}", result.file, result.variant, result);
+    });
 
-    for file in &files {
-        let json_path = format!("{}/{}", dir, file);
-        let expected = parse_expected_direction(file);
-        eprint!("  {} ", file);
+    let failures = report.failures();
+    eprintln!();
+    eprintln!(
+        "Results: {} passed, {} failed out of {} total",
+        report.results.len() - failures.len(),
+        failures.len(),
+        report.results.len()
+    );
+    eprintln!("{}", report);
 
-        let result = std::panic::catch_unwind(|| {
-            let gs = read_game_state(&json_path);
-            logic::get_move(&gs, "single_gamestate_nodes".to_string())
-        });
+    if let Some(path) = save_evaluations {
+        match report.save_evaluations(&path) {
+            Ok(()) => eprintln!("Saved evaluations to {}", path),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
 
-        match (&result, expected) {
-            (Ok(actual), Some(expected_dir)) => {
-                if *actual == expected_dir {
-                    eprintln!("... ok ({})", actual);
-                    pass += 1;
-                } else {
-                    eprintln!("... FAIL (expected: {}, got: {})", expected_dir, actual);
-                    failures.push(format!("{}: expected {} got {}", file, expected_dir, actual));
-                    fail += 1;
-                }
-            }
-            (Ok(actual), None) => {
-                eprintln!("... ok (no expected direction, picked: {})", actual);
-                pass += 1;
-            }
-            (Err(_), _) => {
-                eprintln!("... PANICKED");
-                failures.push(format!("{}: panicked", file));
-                panicked += 1;
+    if let Some(baseline) = baseline {
+        let changed = report.changed_decisions(&baseline);
+        eprintln!();
+        eprintln!("Changed decisions since baseline: {}", changed.len());
+        for (result, diff) in changed {
+            eprintln!("  {} [{}]: {}", result.file, result.variant, result);
+            for change in &diff.changes {
+                eprintln!("    {}", change);
             }
         }
     }
 
-    eprintln!();
-    eprintln!(
-        "Results: {} passed, {} failed, {} panicked out of {} total",
-        pass,
-        fail,
-        panicked,
-        files.len()
-    );
-
     if !failures.is_empty() {
         eprintln!();
         eprintln!("Failures:");
         for f in &failures {
-            eprintln!("  {}", f);
+            eprintln!("  {} [{}]: {}", f.file, f.variant, f);
         }
         process::exit(1);
     }
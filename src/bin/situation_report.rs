@@ -0,0 +1,145 @@
+use battlesnake_game_of_chicken_lib::{
+    OriginalGameState,
+    game_log::parse_log,
+    logic::{
+        general::{direction::Direction, field::BasicField, game_state::GameState},
+        single_gamestate_nodes::{
+            GamestateNodesSnake,
+            situation::{SituationSet, coverage::Coverage},
+        },
+    },
+    read_game_state,
+    regression::Manifest,
+};
+use std::{env, fs, path::Path, process};
+
+// Usage: situation_report [--situations <file>] [--logs <dir>] [<dir> | <manifest.json>]...
+// Runs the labelled regression states and the recorded turns of game logs through the special
+// situations (SITUATIONS_FILE or the built-in ones by default), the fast track trigger and the
+// opponent situations of the tree, and reports matches and agreement per situation and set.
+// Picked moves of won games are labelled good, the last picked move of a lost game bad.
+// Without arguments requests/manifest.json, requests/automated and game_logs are used if they exist.
+fn main() {
+    let mut situations: Option<String> = None;
+    let mut logs: Option<String> = None;
+    let mut targets: Vec<String> = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--situations" => situations = args.next(),
+            "--logs" => logs = args.next(),
+            _ => targets.push(arg),
+        }
+    }
+    if targets.is_empty() && logs.is_none() {
+        let exists = |path: &&str| Path::new(path).exists();
+        targets = ["requests/manifest.json", "requests/automated"]
+            .into_iter()
+            .filter(exists)
+            .map(String::from)
+            .collect();
+        logs = Some("game_logs").filter(exists).map(String::from);
+    }
+
+    let special = match &situations {
+        Some(path) => SituationSet::load(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        }),
+        None => GamestateNodesSnake::special_situation_set(),
+    };
+    let fast_track = SituationSet::new(vec![GamestateNodesSnake::fast_track_trigger_situation()]);
+    let opponents = GamestateNodesSnake::opponent_situation_set();
+    let mut coverage = Coverage::default()
+        .set("special", &special)
+        .set("fast track", &fast_track)
+        .set("opponents", &opponents);
+
+    for target in &targets {
+        let (dir, manifest) = Manifest::resolve(target).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        });
+        for entry in manifest.tests.iter().filter(|entry| !entry.expectation.skip) {
+            let (good, bad) = entry.expectation.labels();
+            let path = Path::new(&dir).join(&entry.file);
+            let gamestate = read_game_state(&path.to_string_lossy());
+            coverage.add(
+                &entry.file,
+                &GameState::<BasicField>::from(&gamestate),
+                &good,
+                &bad,
+            );
+        }
+    }
+
+    if let Some(log_dir) = &logs {
+        let entries = fs::read_dir(log_dir).unwrap_or_else(|e| {
+            eprintln!("Cannot read {}: {}", log_dir, e);
+            process::exit(1);
+        });
+        let mut log_files: Vec<String> = entries
+            .flatten()
+            .map(|entry| entry.path().to_string_lossy().into_owned())
+            .filter(|path| path.ends_with(".log"))
+            .collect();
+        log_files.sort();
+        for path in &log_files {
+            let lost = path.ends_with("_lost.log");
+            let games = parse_log(path).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                process::exit(1);
+            });
+            for game in &games {
+                for (index, record) in game.turns.iter().enumerate() {
+                    let Ok(gamestate) = serde_json::from_str::<OriginalGameState>(&record.json)
+                    else {
+                        continue;
+                    };
+                    let picked: Vec<Direction> =
+                        record.picked.map(Direction::from).into_iter().collect();
+                    let last = index + 1 == game.turns.len();
+                    let (good, bad) = match (lost, last) {
+                        (false, _) => (picked, Vec::new()),
+                        (true, true) => (Vec::new(), picked),
+                        (true, false) => (Vec::new(), Vec::new()),
+                    };
+                    let name = format!("{} turn {}", game.id, record.turn);
+                    coverage.add(
+                        &name,
+                        &GameState::<BasicField>::from(&gamestate),
+                        &good,
+                        &bad,
+                    );
+                }
+            }
+        }
+    }
+
+    eprintln!(
+        "Checked {} situations against {} states",
+        coverage.situations.len(),
+        coverage.states
+    );
+    println!("{}", coverage);
+    for stats in &coverage.situations {
+        if stats.disagreeing.is_empty() {
+            continue;
+        }
+        println!(
+            "{} ({}) disagrees with: {}",
+            stats.detail,
+            stats.set,
+            stats.disagreeing.join(", ")
+        );
+    }
+    let unused: Vec<String> = coverage
+        .situations
+        .iter()
+        .filter(|stats| stats.matches() == 0)
+        .map(|stats| format!("{} ({})", stats.detail, stats.set))
+        .collect();
+    if !unused.is_empty() {
+        println!("Never matched: {}", unused.join(", "));
+    }
+}
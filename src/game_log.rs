@@ -0,0 +1,101 @@
+use crate::OriginalDirection;
+use std::{
+    collections::HashMap,
+    fs,
+    io::{BufRead, BufReader},
+};
+
+/// A move request of a game together with the move that answered it
+pub struct TurnRecord {
+    pub turn: i32,
+    /// The request as logged, an `OriginalGameState`
+    pub json: String,
+    pub picked: Option<OriginalDirection>,
+}
+
+pub struct Game {
+    pub id: String,
+    /// Sorted by turn
+    pub turns: Vec<TurnRecord>,
+}
+
+fn parse_result_line(line: &str) -> Option<(String, i32, OriginalDirection)> {
+    // "... ID <game_id> Turn <turn_num> Result -> <direction>"
+    let id_pos = line.find("] ID ")?;
+    let after_id = &line[id_pos + 5..];
+    let space = after_id.find(' ')?;
+    let game_id = after_id[..space].to_string();
+
+    let turn_pos = after_id.find("Turn ")?;
+    let after_turn = &after_id[turn_pos + 5..];
+    let arrow = after_turn.find(" Result -> ")?;
+    let turn: i32 = after_turn[..arrow].parse().ok()?;
+    let dir_str = after_turn[arrow + 11..].trim();
+
+    let direction = match dir_str {
+        "up" => OriginalDirection::Up,
+        "down" => OriginalDirection::Down,
+        "left" => OriginalDirection::Left,
+        "right" => OriginalDirection::Right,
+        _ => return None,
+    };
+
+    Some((game_id, turn, direction))
+}
+
+/// Reads the games of a server log, turns are taken from the logged requests and results
+pub fn parse_log(path: &str) -> Result<Vec<Game>, String> {
+    let file = fs::File::open(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    let reader = BufReader::new(file);
+    let lines: Vec<String> = reader
+        .lines()
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Cannot read {}: {}", path, e))?;
+
+    // Group turns by game ID
+    let mut game_turns: HashMap<String, Vec<TurnRecord>> = HashMap::new();
+    let mut game_order: Vec<String> = Vec::new();
+
+    for line in &lines {
+        if let Some((game_id, turn, json)) = parse_turn_line(line) {
+            if !game_turns.contains_key(&game_id) {
+                game_order.push(game_id.clone());
+            }
+            game_turns.entry(game_id).or_default().push(TurnRecord {
+                turn,
+                json,
+                picked: None,
+            });
+        } else if let Some((game_id, turn, direction)) = parse_result_line(line)
+            && let Some(turns) = game_turns.get_mut(&game_id)
+            && let Some(record) = turns.iter_mut().find(|r| r.turn == turn)
+        {
+            record.picked = Some(direction);
+        }
+    }
+
+    Ok(game_order
+        .into_iter()
+        .map(|id| {
+            let mut turns = game_turns.remove(&id).unwrap();
+            turns.sort_by_key(|t| t.turn);
+            Game { id, turns }
+        })
+        .collect())
+}
+
+fn parse_turn_line(line: &str) -> Option<(String, i32, String)> {
+    // "... ID <game_id> Turn <turn_num> Request -> <json>"
+    let id_pos = line.find("] ID ")?;
+    let after_id = &line[id_pos + 5..];
+    let space = after_id.find(' ')?;
+    let game_id = after_id[..space].to_string();
+
+    let turn_pos = after_id.find("Turn ")?;
+    let after_turn = &after_id[turn_pos + 5..];
+    let arrow = after_turn.find(" Request -> ")?;
+    let turn: i32 = after_turn[..arrow].parse().ok()?;
+    let json = after_turn[arrow + 12..].to_string();
+
+    Some((game_id, turn, json))
+}
@@ -3,13 +3,24 @@
 extern crate test;
 
 use core::fmt;
-use serde::{Deserialize, Serialize, Serializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
-use std::{collections::HashMap, env};
+use std::{collections::HashMap, env, str::FromStr};
 
-use crate::logic::{general::direction::Direction, get_move};
+use crate::logic::{
+    general::{
+        board::{HEIGHT, WIDTH},
+        direction::Direction,
+        snakes::SNAKES,
+    },
+    get_move,
+};
 
+pub mod game_log;
 pub mod logic;
+pub mod metrics;
+pub mod regression;
+pub mod turn_log;
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct OriginalGame {
@@ -82,6 +93,17 @@ impl From<Direction> for OriginalDirection {
     }
 }
 
+impl From<OriginalDirection> for Direction {
+    fn from(dir: OriginalDirection) -> Self {
+        match dir {
+            OriginalDirection::Up => Direction::Up,
+            OriginalDirection::Down => Direction::Down,
+            OriginalDirection::Left => Direction::Left,
+            OriginalDirection::Right => Direction::Right,
+        }
+    }
+}
+
 impl fmt::Display for OriginalDirection {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -107,6 +129,30 @@ impl Serialize for OriginalDirection {
     }
 }
 
+impl FromStr for OriginalDirection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "up" => Ok(OriginalDirection::Up),
+            "down" => Ok(OriginalDirection::Down),
+            "left" => Ok(OriginalDirection::Left),
+            "right" => Ok(OriginalDirection::Right),
+            _ => Err(format!("Invalid direction: {}", s)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for OriginalDirection {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 const DIR: &str = "requests/";
 
 pub fn read_game_state(path: &str) -> OriginalGameState {
@@ -121,40 +167,144 @@ pub fn read_game_state(path: &str) -> OriginalGameState {
     gamestate
 }
 
+/// Reasons why a request cannot be handled by the rule engines
+#[derive(Debug, Clone, PartialEq)]
+pub enum RequestError {
+    UnsupportedBoardSize { width: i32, height: u32 },
+    TooManySnakes(usize),
+    YouNotOnBoard(String),
+    DuplicateSnakeId(String),
+    EmptyBody(String),
+    LengthMismatch { id: String, length: i32, body: usize },
+    HeadMismatch(String),
+    InvalidHealth { id: String, health: i32 },
+    InvalidBody(String),
+    Overlap { id: String, other: String },
+    OutOfBounds { what: String, coord: OriginalCoord },
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestError::UnsupportedBoardSize { width, height } => {
+                write!(f, "Unsupported board size {}x{}", width, height)
+            }
+            RequestError::TooManySnakes(count) => write!(f, "Too many snakes: {}", count),
+            RequestError::YouNotOnBoard(id) => write!(f, "Snake {} is not on the board", id),
+            RequestError::DuplicateSnakeId(id) => write!(f, "Duplicate snake id {}", id),
+            RequestError::EmptyBody(id) => write!(f, "Snake {} has no body", id),
+            RequestError::LengthMismatch { id, length, body } => write!(
+                f,
+                "Snake {} has length {} but {} body parts",
+                id, length, body
+            ),
+            RequestError::HeadMismatch(id) => {
+                write!(f, "Head of snake {} is not its first body part", id)
+            }
+            RequestError::InvalidHealth { id, health } => {
+                write!(f, "Snake {} has invalid health {}", id, health)
+            }
+            RequestError::InvalidBody(id) => {
+                write!(f, "Body of snake {} is not a connected path", id)
+            }
+            RequestError::Overlap { id, other } => {
+                write!(f, "Snakes {} and {} overlap", id, other)
+            }
+            RequestError::OutOfBounds { what, coord } => {
+                write!(f, "{} at ({}, {}) is out of bounds", what, coord.x, coord.y)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RequestError {}
+
+/// Checks everything the rule engines rely on when converting a request.
+/// States passing this check can be given to every variant without panicking.
+pub fn validate_game_state(state: &OriginalGameState) -> Result<(), RequestError> {
+    let board = &state.board;
+    if board.width != WIDTH as i32 || board.height != HEIGHT as u32 {
+        return Err(RequestError::UnsupportedBoardSize {
+            width: board.width,
+            height: board.height,
+        });
+    }
+    if board.snakes.len() > SNAKES {
+        return Err(RequestError::TooManySnakes(board.snakes.len()));
+    }
+    if !board.snakes.iter().any(|snake| snake.id == state.you.id) {
+        return Err(RequestError::YouNotOnBoard(state.you.id.clone()));
+    }
+    let in_bounds = |coord: &OriginalCoord| {
+        (0..board.width).contains(&coord.x) && (0..board.height as i32).contains(&coord.y)
+    };
+    for coord in board.food.iter().chain(board.hazards.iter()) {
+        if !in_bounds(coord) {
+            return Err(RequestError::OutOfBounds {
+                what: "Food or hazard".to_string(),
+                coord: *coord,
+            });
+        }
+    }
+    for (i, snake) in board.snakes.iter().enumerate() {
+        let id = || snake.id.clone();
+        if board.snakes[..i].iter().any(|other| other.id == snake.id) {
+            return Err(RequestError::DuplicateSnakeId(id()));
+        }
+        if snake.body.is_empty() {
+            return Err(RequestError::EmptyBody(id()));
+        }
+        if snake.body.len() as i32 != snake.length {
+            return Err(RequestError::LengthMismatch {
+                id: id(),
+                length: snake.length,
+                body: snake.body.len(),
+            });
+        }
+        if snake.body[0] != snake.head {
+            return Err(RequestError::HeadMismatch(id()));
+        }
+        if !(1..=100).contains(&snake.health) {
+            return Err(RequestError::InvalidHealth {
+                id: id(),
+                health: snake.health,
+            });
+        }
+        if let Some(coord) = snake.body.iter().find(|coord| !in_bounds(coord)) {
+            return Err(RequestError::OutOfBounds {
+                what: format!("Snake {}", snake.id),
+                coord: *coord,
+            });
+        }
+        // Adjacent segments differ by 1 in one axis or are stacked (eating/start), no crossings
+        for (j, w) in snake.body.windows(2).enumerate() {
+            let distance = (w[0].x - w[1].x).abs() + (w[0].y - w[1].y).abs();
+            if distance > 1 || (distance == 1 && snake.body[..j].contains(&w[1])) {
+                return Err(RequestError::InvalidBody(id()));
+            }
+        }
+        for other in board.snakes[..i].iter() {
+            if snake.body.iter().any(|coord| other.body.contains(coord)) {
+                return Err(RequestError::Overlap {
+                    id: id(),
+                    other: other.id.clone(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
 fn check_game_state(state: &OriginalGameState) {
-    let height = state.board.height as i32;
+    if let Err(error) = validate_game_state(state) {
+        panic!("Invalid game state: {}", error);
+    }
+    // Stored states additionally follow the standard rules
     for snake in state.board.snakes.iter() {
         let snake_sum = snake.head.x + snake.head.y;
         assert!(snake_sum % 2 == state.turn % 2);
-        assert!(snake.body.len() as i32 == snake.length);
-        assert!(snake.body[0] == snake.head);
-        assert!(snake.health > 0);
-        assert!(snake.body.windows(2).all(|w| {
-            let dx = (w[0].x - w[1].x).abs();
-            let dy = (w[0].y - w[1].y).abs();
-            // Adjacent segments differ by 1 in one axis, or are stacked (eating/start)
-            (dx == 1 && dy == 0) || (dx == 0 && dy == 1) || (dx == 0 && dy == 0)
-        }));
-        assert!(snake.body.iter().all(|coord| {
-            coord.x >= 0 && coord.x < state.board.width && coord.y >= 0 && coord.y < height
-        }));
-        // Different snakes must not overlap
-        assert!(snake.body.iter().all(|coord| {
-            !state
-                .board
-                .snakes
-                .iter()
-                .filter(|other| other.id != snake.id)
-                .any(|other| other.body.contains(coord))
-        }));
         assert!(snake.length >= 3);
     }
-    assert!(state.board.food.iter().all(|coord| {
-        coord.x >= 0 && coord.x < state.board.width && coord.y >= 0 && coord.y < height
-    }));
-    assert!(state.board.hazards.iter().all(|coord| {
-        coord.x >= 0 && coord.x < state.board.width && coord.y >= 0 && coord.y < height
-    }));
 }
 
 pub fn get_move_from_json_file(path: &str) -> OriginalDirection {
@@ -189,3 +339,93 @@ pub fn get_move_from_json_file_with_timeout(path: &str, timeout_ms: u64) -> Orig
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(path: &str) -> OriginalGameState {
+        let file = std::fs::File::open(path).unwrap();
+        serde_json::from_reader(std::io::BufReader::new(file)).unwrap()
+    }
+
+    #[test]
+    fn test_validate_stored_state() {
+        let state = load("requests/failure_1.json");
+        assert_eq!(validate_game_state(&state), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_board_and_snakes() {
+        let mut state = load("requests/example_move_request.json");
+        state.board.width = 19;
+        assert!(matches!(
+            validate_game_state(&state),
+            Err(RequestError::UnsupportedBoardSize { .. })
+        ));
+
+        let mut state = load("requests/example_move_request.json");
+        state.you.id = "unknown".to_string();
+        assert!(matches!(
+            validate_game_state(&state),
+            Err(RequestError::YouNotOnBoard(_))
+        ));
+
+        let mut state = load("requests/example_move_request.json");
+        state.board.snakes[1].id = state.board.snakes[0].id.clone();
+        assert!(matches!(
+            validate_game_state(&state),
+            Err(RequestError::DuplicateSnakeId(_))
+        ));
+
+        let mut state = load("requests/example_move_request.json");
+        state.board.food.push(OriginalCoord { x: 11, y: 0 });
+        assert!(matches!(
+            validate_game_state(&state),
+            Err(RequestError::OutOfBounds { .. })
+        ));
+    }
+
+    #[test]
+    fn test_validate_snake_bodies() {
+        let mut state = load("requests/example_move_request.json");
+        state.board.snakes[0].health = 0;
+        assert!(matches!(
+            validate_game_state(&state),
+            Err(RequestError::InvalidHealth { health: 0, .. })
+        ));
+
+        // A snake of a single part is unusual but consistent
+        let mut state = load("requests/example_move_request.json");
+        state.board.snakes[0].body.truncate(1);
+        state.board.snakes[0].length = 1;
+        assert_eq!(validate_game_state(&state), Ok(()));
+        for variant in logic::VARIANTS {
+            get_move(&state, variant.to_string());
+        }
+
+        let mut state = load("requests/example_move_request.json");
+        state.board.snakes[0].body[2] = OriginalCoord { x: 5, y: 5 };
+        assert!(matches!(
+            validate_game_state(&state),
+            Err(RequestError::InvalidBody(_))
+        ));
+
+        let mut state = load("requests/example_move_request.json");
+        state.board.snakes[0].length = 4;
+        assert!(matches!(
+            validate_game_state(&state),
+            Err(RequestError::LengthMismatch { .. })
+        ));
+
+        // Moved next to the second snake with the tail on its head
+        let mut state = load("requests/example_move_request.json");
+        let body = [(3, 3), (4, 3), (5, 3)].map(|(x, y)| OriginalCoord { x, y });
+        state.board.snakes[0].body = body.to_vec();
+        state.board.snakes[0].head = body[0];
+        assert!(matches!(
+            validate_game_state(&state),
+            Err(RequestError::Overlap { .. })
+        ));
+    }
+}
@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::{cell::RefCell, collections::BTreeMap, env, fs};
+
+/// Parameters that override environment variables for a single snake, e.g. `SIMULATION_TIME_MS`
+pub type Params = BTreeMap<String, String>;
+
+thread_local! {
+    static PARAMS: RefCell<Params> = const { RefCell::new(Params::new()) };
+}
+
+/// Looks up a parameter of the snake served on this thread, falling back to the environment
+pub fn param(name: &str) -> Option<String> {
+    PARAMS
+        .with(|params| params.borrow().get(name).cloned())
+        .or_else(|| env::var(name).ok())
+}
+
+/// Puts the previous parameters back when dropped, also while unwinding from a panic
+struct RestoreParams {
+    previous: Params,
+}
+
+impl Drop for RestoreParams {
+    fn drop(&mut self) {
+        let previous = std::mem::take(&mut self.previous);
+        PARAMS.with(|current| current.replace(previous));
+    }
+}
+
+/// Runs `f` with the given parameters visible to `param` on this thread
+pub fn with_params<R>(params: Params, f: impl FnOnce() -> R) -> R {
+    let _restore = RestoreParams {
+        previous: PARAMS.with(|current| current.replace(params)),
+    };
+    f()
+}
+
+fn default_variant() -> String {
+    "breadth_first".to_string()
+}
+
+fn default_color() -> String {
+    "#f5982f".to_string()
+}
+
+fn default_head() -> String {
+    "chicken".to_string()
+}
+
+fn default_tail() -> String {
+    "duck".to_string()
+}
+
+/// A snake served by the server, the path is the prefix of its routes
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct SnakeConfig {
+    pub path: String,
+    #[serde(default = "default_variant")]
+    pub variant: String,
+    #[serde(default = "default_color")]
+    pub color: String,
+    #[serde(default = "default_head")]
+    pub head: String,
+    #[serde(default = "default_tail")]
+    pub tail: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: Params,
+}
+
+impl SnakeConfig {
+    /// The snake at `/`, configured by `VARIANT`, `SNAKE_COLOR`, `SNAKE_HEAD` and `SNAKE_TAIL`
+    pub fn from_env() -> Self {
+        Self {
+            path: "/".to_string(),
+            variant: env::var("VARIANT").unwrap_or_else(|_| default_variant()),
+            color: env::var("SNAKE_COLOR").unwrap_or_else(|_| default_color()),
+            head: env::var("SNAKE_HEAD").unwrap_or_else(|_| default_head()),
+            tail: env::var("SNAKE_TAIL").unwrap_or_else(|_| default_tail()),
+            params: Params::new(),
+        }
+    }
+
+    /// Parameter of this snake, falling back to the environment
+    pub fn param(&self, name: &str) -> Option<String> {
+        self.params
+            .get(name)
+            .cloned()
+            .or_else(|| env::var(name).ok())
+    }
+
+    /// Route prefix with a leading and without a trailing slash, `/` for the root
+    pub fn base(&self) -> String {
+        format!("/{}", self.path.trim_matches('/'))
+    }
+
+    pub fn info(&self) -> Value {
+        json!({
+            "apiversion": "1",
+            "author": "WbaN",
+            "color": self.color,
+            "head": self.head,
+            "tail": self.tail,
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    pub snakes: Vec<SnakeConfig>,
+}
+
+impl ServerConfig {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+        let config: Self =
+            serde_json::from_str(&content).map_err(|e| format!("Cannot parse {}: {}", path, e))?;
+        config
+            .check()
+            .map_err(|e| format!("Invalid {}: {}", path, e))?;
+        Ok(config)
+    }
+
+    /// Reads the file named by `SNAKES_CONFIG`, without it only the snake from the environment is served
+    pub fn from_env() -> Result<Self, String> {
+        match env::var("SNAKES_CONFIG") {
+            Ok(path) => Self::load(&path),
+            Err(_) => Ok(Self {
+                snakes: vec![SnakeConfig::from_env()],
+            }),
+        }
+    }
+
+    fn check(&self) -> Result<(), String> {
+        let mut bases = Vec::new();
+        for snake in &self.snakes {
+            if !super::VARIANTS.contains(&snake.variant.as_str()) {
+                return Err(format!(
+                    "unknown variant {} for {}",
+                    snake.variant,
+                    snake.base()
+                ));
+            }
+            let base = snake.base();
+            if base[1..].contains('/') {
+                return Err(format!("path {} has more than one segment", base));
+            }
+            if base == "/metrics" {
+                return Err("path /metrics is reserved for the metrics endpoint".to_string());
+            }
+            if bases.contains(&base) {
+                return Err(format!("path {} is used twice", base));
+            }
+            bases.push(base);
+        }
+        Ok(())
+    }
+
+    pub fn snake(&self, base: &str) -> Option<&SnakeConfig> {
+        self.snakes.iter().find(|snake| snake.base() == base)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_params() {
+        let params = Params::from([("SIMULATION_TIME_MS".to_string(), "42".to_string())]);
+        assert_eq!(
+            with_params(params, || param("SIMULATION_TIME_MS")),
+            Some("42".to_string())
+        );
+        assert_eq!(
+            PARAMS.with(|params| params.borrow().get("SIMULATION_TIME_MS").cloned()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_with_params_panic() {
+        let params = Params::from([("SEARCH_PRIORITY".to_string(), "panic".to_string())]);
+        let result = std::panic::catch_unwind(|| {
+            with_params(params, || panic!("{:?}", param("SEARCH_PRIORITY")))
+        });
+        assert!(result.is_err());
+        // A panicking evaluation does not leave its override to the next ones on this thread
+        assert_ne!(param("SEARCH_PRIORITY"), Some("panic".to_string()));
+    }
+
+    #[test]
+    fn test_server_config() {
+        let config: ServerConfig = serde_json::from_str(
+            r##"{"snakes": [
+                {"path": "/", "variant": "single_gamestate_nodes"},
+                {"path": "depth_first/", "variant": "depth_first", "color": "#000000"}
+            ]}"##,
+        )
+        .unwrap();
+        assert!(config.check().is_ok());
+        assert_eq!(config.snake("/depth_first").unwrap().color, "#000000");
+        assert_eq!(config.snake("/").unwrap().head, "chicken");
+
+        let mut duplicate = config.clone();
+        duplicate.snakes[1].path = "".to_string();
+        assert!(duplicate.check().is_err());
+
+        let mut unknown = config;
+        unknown.snakes[1].variant = "unknown".to_string();
+        assert!(unknown.check().is_err());
+    }
+}
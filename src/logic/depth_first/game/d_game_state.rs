@@ -24,6 +24,14 @@ pub struct DGameState<T: DField> {
 }
 
 impl<T: DField> DGameState<T> {
+    pub fn board(&self) -> &DBoard<T> {
+        &self.board
+    }
+
+    pub fn snakes(&self) -> &DSnakes {
+        &self.snakes
+    }
+
     /// Convenience method to play a game with a list of moves
     /// Moves are given as a list of strings where each string represents the moves for a snake
     /// Example input: ["UDDL", "DUU", "", ""]
@@ -195,8 +203,15 @@ impl<T: DField> DGameState<T> {
         }
 
         // Remove all snakes that need to be removed
+        // Food under a removed head is eaten nevertheless, feeding happens before eliminations
         for id in 0..SNAKES {
             if let Some(snake) = snakes_to_remove[id as usize] {
+                if let DSnake::Alive { head, .. } = snake {
+                    let field = self.board.cell(head.x, head.y).unwrap();
+                    if field.get().get_type() == T::FOOD {
+                        field.set(T::empty());
+                    }
+                }
                 self.snakes.cell(id).set(snake.to_dead());
                 self.board.remove_snake(snake);
             }
@@ -4,5 +4,5 @@ pub mod d_direction;
 pub mod d_field;
 pub mod d_game_state;
 pub mod d_moves_set;
-mod d_snake;
-mod d_snakes;
+pub mod d_snake;
+pub mod d_snakes;
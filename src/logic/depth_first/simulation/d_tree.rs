@@ -1,6 +1,9 @@
 use log::{debug, trace};
 
-use crate::logic::depth_first::game::d_direction::{D_DIRECTION_LIST, DDirection};
+use crate::logic::{
+    depth_first::game::d_direction::{D_DIRECTION_LIST, DDirection},
+    watchdog,
+};
 
 use super::{
     d_node_id::DNodeId,
@@ -28,6 +31,9 @@ impl DTreeTime {
     }
 
     pub fn is_timed_out(&self) -> bool {
+        if watchdog::stop_requested() {
+            return true;
+        }
         match self.duration {
             Some(duration) => self.start.elapsed() > duration,
             None => false,
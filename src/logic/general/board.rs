@@ -11,14 +11,42 @@ use std::cell::Cell;
 pub const HEIGHT: i8 = 11;
 pub const WIDTH: i8 = 11;
 
+/// Cells with hazards, one bit per cell. Hazards do not change during a simulation.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Hazards([u8; 16]);
+
+impl Hazards {
+    fn bit(coord: Coord) -> Option<usize> {
+        if (0..WIDTH).contains(&coord.x) && (0..HEIGHT).contains(&coord.y) {
+            Some(coord.y as usize * WIDTH as usize + coord.x as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn insert(&mut self, coord: Coord) {
+        if let Some(bit) = Self::bit(coord) {
+            self.0[bit / 8] |= 1 << (bit % 8);
+        }
+    }
+
+    pub fn contains(&self, coord: Coord) -> bool {
+        Self::bit(coord).is_some_and(|bit| self.0[bit / 8] & (1 << (bit % 8)) != 0)
+    }
+}
+
 #[derive(Clone)]
 pub struct Board<T: Field> {
     fields: [[Cell<T>; WIDTH as usize]; HEIGHT as usize],
+    hazards: Hazards,
 }
 
 impl<T: Field> Board<T> {
     pub fn from_request(board: &OriginalBoard, you: &OriginalBattlesnake) -> Self {
-        let d_board = Board::default();
+        let mut d_board = Board::default();
+        for hazard in board.hazards.iter() {
+            d_board.hazards.insert(hazard.into());
+        }
         for food in board.food.iter() {
             d_board
                 .cell(food.x as i8, food.y as i8)
@@ -63,7 +91,21 @@ impl<T: Field> Board<T> {
         self.cell(coord.x, coord.y)
     }
 
+    pub fn hazards(&self) -> Hazards {
+        self.hazards
+    }
+
+    pub fn set_hazards(&mut self, hazards: Hazards) {
+        self.hazards = hazards;
+    }
+
     pub fn remove_snake(&self, snake: Snake) {
+        self.remove_snake_with(snake, |_, _| ());
+    }
+
+    /// Removes a snake like `remove_snake`, passing every cleared cell with its previous field
+    /// to `cleared`
+    pub fn remove_snake_with(&self, snake: Snake, mut cleared: impl FnMut(Coord, T)) {
         match snake {
             Snake::Alive {
                 id: snake_id,
@@ -78,6 +120,7 @@ impl<T: Field> Board<T> {
                 let field = self.cell(tail.x, tail.y).unwrap().get();
                 if let BasicField::Snake { id, next } = field.value() {
                     if id == snake_id {
+                        cleared(tail, field);
                         self.cell(tail.x, tail.y).unwrap().set(T::empty());
                         if let Some(next) = next {
                             tail += next.into();
@@ -100,6 +143,7 @@ impl<T: Field> Default for Board<T> {
     fn default() -> Self {
         Board {
             fields: std::array::from_fn(|_| std::array::from_fn(|_| Cell::new(T::empty()))),
+            hazards: Hazards::default(),
         }
     }
 }
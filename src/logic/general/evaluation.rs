@@ -1,10 +1,44 @@
-use crate::logic::general::direction::{Direction, DIRECTIONS};
+use crate::logic::general::{
+    direction::{Direction, DIRECTIONS},
+    evaluation_report::{EvaluationReport, ScoreDetail, SectionReport},
+};
+use serde::{Deserialize, Serialize, Serializer};
 use std::fmt::{Display, Formatter, Result as FmtResult};
 use tabled::{
     builder::Builder,
     settings::{Alignment, Style, object::Columns},
 };
 
+/// Which part of the evaluation decided the move
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Reason {
+    /// All other directions were eliminated
+    Elimination,
+    /// Highest score among the directions left after elimination
+    Score,
+    /// Nothing was left to pick from
+    Default,
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Reason::Elimination => write!(f, "elimination"),
+            Reason::Score => write!(f, "score"),
+            Reason::Default => write!(f, "default"),
+        }
+    }
+}
+
+/// Score totals and elimination priorities of a section, indexed in the order of `DIRECTIONS`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SectionSummary {
+    pub name: String,
+    pub scores: [i32; 4],
+    pub eliminations: [Option<u8>; 4],
+}
+
 pub struct Evaluation {
     sections: Vec<EvaluationSection>,
 }
@@ -100,6 +134,45 @@ impl Evaluation {
         Direction::Up
     }
 
+    pub fn reason(&self) -> Reason {
+        match self
+            .directions_after_elimination()
+            .iter()
+            .filter(|&&x| x)
+            .count()
+        {
+            0 => Reason::Default,
+            1 => Reason::Elimination,
+            _ => Reason::Score,
+        }
+    }
+
+    pub fn report(&self) -> EvaluationReport {
+        EvaluationReport {
+            sections: self
+                .sections
+                .iter()
+                .map(|section| SectionReport {
+                    name: section.name.clone(),
+                    eliminations: section.elimination_priority,
+                    details: std::array::from_fn(|i| {
+                        section.score_details[i]
+                            .iter()
+                            .map(|(score, detail)| ScoreDetail {
+                                score: *score,
+                                detail: detail.clone(),
+                            })
+                            .collect()
+                    }),
+                })
+                .collect(),
+            available: self.directions_after_elimination(),
+            totals: self.total_scores(),
+            result: self.result().into(),
+            reason: self.reason(),
+        }
+    }
+
     fn total_scores(&self) -> [i32; 4] {
         let mut totals = [0; 4];
         for section in &self.sections {
@@ -112,6 +185,12 @@ impl Evaluation {
 
 }
 
+impl Serialize for Evaluation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.report().serialize(serializer)
+    }
+}
+
 struct EvaluationSection {
     name: String,
     elimination_priority: [Option<u8>; 4],
@@ -0,0 +1,400 @@
+use crate::{
+    OriginalDirection,
+    logic::general::{
+        direction::DIRECTIONS,
+        evaluation::{Reason, SectionSummary},
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// Longest shout the game engine accepts
+pub const MAX_SHOUT_LENGTH: usize = 256;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScoreDetail {
+    pub score: i32,
+    pub detail: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SectionReport {
+    pub name: String,
+    pub eliminations: [Option<u8>; 4],
+    pub details: [Vec<ScoreDetail>; 4],
+}
+
+impl SectionReport {
+    /// Scores of all details with the given label per direction
+    fn detail_scores(&self, label: &str) -> [i32; 4] {
+        std::array::from_fn(|i| {
+            self.details[i]
+                .iter()
+                .filter(|d| d.detail == label)
+                .map(|d| d.score)
+                .sum()
+        })
+    }
+
+    fn labels(&self) -> Vec<&str> {
+        let mut labels: Vec<&str> = Vec::new();
+        for detail in self.details.iter().flatten() {
+            if !labels.contains(&detail.detail.as_str()) {
+                labels.push(&detail.detail);
+            }
+        }
+        labels
+    }
+}
+
+/// Everything `Evaluation` knows about a decision, per direction arrays are ordered as `DIRECTIONS`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EvaluationReport {
+    pub sections: Vec<SectionReport>,
+    /// Directions left after the eliminations of all sections
+    pub available: [bool; 4],
+    pub totals: [i32; 4],
+    pub result: OriginalDirection,
+    pub reason: Reason,
+}
+
+impl EvaluationReport {
+    pub fn section_summaries(&self) -> Vec<SectionSummary> {
+        self.sections
+            .iter()
+            .map(|section| SectionSummary {
+                name: section.name.clone(),
+                scores: std::array::from_fn(|i| {
+                    section.details[i].iter().map(|d| d.score).sum()
+                }),
+                eliminations: section.eliminations,
+            })
+            .collect()
+    }
+
+    /// Short explanation of the move for the `shout` of the move response.
+    /// Names the section that eliminated the other directions, or the score detail that gave the
+    /// picked direction the largest lead over the best other available direction.
+    pub fn shout(&self) -> Option<String> {
+        let picked = DIRECTIONS
+            .iter()
+            .position(|&d| OriginalDirection::from(d) == self.result)?;
+        let shout = match self.reason {
+            Reason::Elimination => {
+                let section = self.sections.iter().rev().find(|section| {
+                    (0..4).any(|i| i != picked && section.eliminations[i].is_some())
+                })?;
+                format!("{}: others eliminated by {}", self.result, section.name)
+            }
+            Reason::Score => {
+                let mut best: Option<(i32, &str)> = None;
+                for section in &self.sections {
+                    for label in section.labels() {
+                        let scores = section.detail_scores(label);
+                        let rival = (0..4)
+                            .filter(|&i| i != picked && self.available[i])
+                            .map(|i| scores[i])
+                            .max()
+                            .unwrap_or(0);
+                        let lead = scores[picked] - rival;
+                        if lead > 0 && best.is_none_or(|(best_lead, _)| lead > best_lead) {
+                            best = Some((lead, label));
+                        }
+                    }
+                }
+                let (_, label) = best?;
+                format!("{}: {}", self.result, label)
+            }
+            Reason::Default => return None,
+        };
+        Some(truncate(shout, MAX_SHOUT_LENGTH))
+    }
+
+    /// Sections with the number of earlier sections of the same name, to match repeated sections
+    fn keyed_sections(&self) -> Vec<((&str, usize), &SectionReport)> {
+        let mut keyed = Vec::new();
+        for (index, section) in self.sections.iter().enumerate() {
+            let occurrence = self.sections[..index]
+                .iter()
+                .filter(|s| s.name == section.name)
+                .count();
+            keyed.push(((section.name.as_str(), occurrence), section));
+        }
+        keyed
+    }
+
+    /// Changes from `self` to `after`, which should evaluate the same state
+    pub fn diff(&self, after: &EvaluationReport) -> EvaluationDiff {
+        let mut changes = Vec::new();
+        if self.result != after.result {
+            changes.push(EvaluationChange::Result {
+                before: self.result,
+                after: after.result,
+            });
+        }
+        if self.reason != after.reason {
+            changes.push(EvaluationChange::Reason {
+                before: self.reason,
+                after: after.reason,
+            });
+        }
+        for (i, direction) in DIRECTIONS.iter().enumerate() {
+            if self.available[i] != after.available[i] {
+                changes.push(EvaluationChange::Available {
+                    direction: (*direction).into(),
+                    after: after.available[i],
+                });
+            }
+        }
+
+        let before_sections = self.keyed_sections();
+        let after_sections = after.keyed_sections();
+        for (key, section) in &before_sections {
+            if !after_sections.iter().any(|(k, _)| k == key) {
+                changes.push(EvaluationChange::SectionRemoved(section.name.clone()));
+            }
+        }
+        for (key, after_section) in &after_sections {
+            let Some((_, before_section)) = before_sections.iter().find(|(k, _)| k == key) else {
+                changes.push(EvaluationChange::SectionAdded(after_section.name.clone()));
+                continue;
+            };
+            for (i, direction) in DIRECTIONS.iter().enumerate() {
+                let (before, after) = (
+                    before_section.eliminations[i],
+                    after_section.eliminations[i],
+                );
+                if before != after {
+                    changes.push(EvaluationChange::Elimination {
+                        section: after_section.name.clone(),
+                        direction: (*direction).into(),
+                        before,
+                        after,
+                    });
+                }
+            }
+            let mut labels = before_section.labels();
+            for label in after_section.labels() {
+                if !labels.contains(&label) {
+                    labels.push(label);
+                }
+            }
+            for label in labels {
+                let before = before_section.detail_scores(label);
+                let after = after_section.detail_scores(label);
+                for (i, direction) in DIRECTIONS.iter().enumerate() {
+                    if before[i] != after[i] {
+                        changes.push(EvaluationChange::Score {
+                            section: after_section.name.clone(),
+                            detail: label.to_string(),
+                            direction: (*direction).into(),
+                            before: before[i],
+                            after: after[i],
+                        });
+                    }
+                }
+            }
+        }
+        EvaluationDiff { changes }
+    }
+}
+
+fn truncate(mut text: String, max_length: usize) -> String {
+    if let Some((index, _)) = text.char_indices().nth(max_length) {
+        text.truncate(index);
+    }
+    text
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvaluationChange {
+    Result {
+        before: OriginalDirection,
+        after: OriginalDirection,
+    },
+    Reason {
+        before: Reason,
+        after: Reason,
+    },
+    Available {
+        direction: OriginalDirection,
+        after: bool,
+    },
+    SectionAdded(String),
+    SectionRemoved(String),
+    Elimination {
+        section: String,
+        direction: OriginalDirection,
+        before: Option<u8>,
+        after: Option<u8>,
+    },
+    Score {
+        section: String,
+        detail: String,
+        direction: OriginalDirection,
+        before: i32,
+        after: i32,
+    },
+}
+
+impl Display for EvaluationChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let priority = |p: &Option<u8>| p.map_or("-".to_string(), |p| p.to_string());
+        match self {
+            EvaluationChange::Result { before, after } => {
+                write!(f, "picked {} -> {}", before, after)
+            }
+            EvaluationChange::Reason { before, after } => {
+                write!(f, "decided by {} -> {}", before, after)
+            }
+            EvaluationChange::Available { direction, after } => {
+                let state = if *after { "available" } else { "eliminated" };
+                write!(f, "{} became {}", direction, state)
+            }
+            EvaluationChange::SectionAdded(name) => write!(f, "section {} added", name),
+            EvaluationChange::SectionRemoved(name) => write!(f, "section {} removed", name),
+            EvaluationChange::Elimination {
+                section,
+                direction,
+                before,
+                after,
+            } => write!(
+                f,
+                "{} eliminates {}: {} -> {}",
+                section,
+                direction,
+                priority(before),
+                priority(after)
+            ),
+            EvaluationChange::Score {
+                section,
+                detail,
+                direction,
+                before,
+                after,
+            } => write!(
+                f,
+                "{} / {} {}: {} -> {}",
+                section, detail, direction, before, after
+            ),
+        }
+    }
+}
+
+/// Changes between two evaluations, the outcome first, then eliminations and scores per section
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvaluationDiff {
+    pub changes: Vec<EvaluationChange>,
+}
+
+impl EvaluationDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl Display for EvaluationDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if self.changes.is_empty() {
+            return writeln!(f, "Evaluation unchanged");
+        }
+        writeln!(f, "Evaluation changes")?;
+        for change in &self.changes {
+            writeln!(f, "  {}", change)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logic::general::{
+        direction::Direction,
+        evaluation::{Evaluation, Reason},
+        evaluation_report::{EvaluationChange, EvaluationReport, MAX_SHOUT_LENGTH, truncate},
+    };
+    use crate::OriginalDirection;
+
+    fn evaluation(alive_for_right: i32) -> Evaluation {
+        let mut evaluation = Evaluation::new();
+        evaluation.new_section("Simulation");
+        evaluation.eliminate(Direction::Up, 2);
+        evaluation.score(Direction::Down, 5, "Alive For");
+        evaluation.score(Direction::Right, alive_for_right, "Alive For");
+        evaluation.new_section("Capture");
+        evaluation.score(Direction::Left, 7, "Flooded Area");
+        evaluation
+    }
+
+    #[test]
+    fn test_report_roundtrip() {
+        let report = evaluation(3).report();
+        assert_eq!(report.result, OriginalDirection::Left);
+        assert_eq!(report.reason, Reason::Score);
+        assert_eq!(report.available, [false, true, true, true]);
+        assert_eq!(report.totals, [0, 5, 7, 3]);
+
+        let json = serde_json::to_string(&evaluation(3)).unwrap();
+        let parsed: EvaluationReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, report);
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = evaluation(3).report();
+        assert!(before.diff(&before).is_empty());
+
+        let after = evaluation(9).report();
+        let diff = before.diff(&after);
+        assert_eq!(
+            diff.changes,
+            vec![
+                EvaluationChange::Result {
+                    before: OriginalDirection::Left,
+                    after: OriginalDirection::Right
+                },
+                EvaluationChange::Score {
+                    section: "Simulation".to_string(),
+                    detail: "Alive For".to_string(),
+                    direction: OriginalDirection::Right,
+                    before: 3,
+                    after: 9
+                },
+            ]
+        );
+        assert_eq!(
+            diff.to_string(),
+            "Evaluation changes\n  picked left -> right\n  Simulation / Alive For right: 3 -> 9\n"
+        );
+
+        let mut removed = after.clone();
+        removed.sections.pop();
+        assert!(
+            after
+                .diff(&removed)
+                .changes
+                .contains(&EvaluationChange::SectionRemoved("Capture".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_shout() {
+        let mut evaluation = evaluation(3);
+        evaluation.new_section("Situations");
+        evaluation.score(Direction::Right, 100, "Kill by Lead");
+        assert_eq!(evaluation.report().shout().unwrap(), "right: Kill by Lead");
+
+        let mut evaluation = Evaluation::new();
+        evaluation.new_section("Simulation");
+        evaluation.eliminate(Direction::Up, 3);
+        evaluation.eliminate(Direction::Down, 2);
+        evaluation.eliminate(Direction::Left, 1);
+        assert_eq!(
+            evaluation.report().shout().unwrap(),
+            "right: others eliminated by Simulation"
+        );
+
+        let long = "x".repeat(300) + "ü";
+        assert_eq!(truncate(long, MAX_SHOUT_LENGTH).chars().count(), MAX_SHOUT_LENGTH);
+    }
+}
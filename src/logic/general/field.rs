@@ -199,6 +199,16 @@ impl Field for BitField {
     }
 }
 
+impl From<BasicField> for BitField {
+    fn from(field: BasicField) -> Self {
+        match field {
+            BasicField::Empty => BitField::empty(),
+            BasicField::Food => BitField::food(),
+            BasicField::Snake { id, next } => BitField::snake(id, next),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum FloodFillField {
     Empty {
@@ -0,0 +1,255 @@
+use crate::{
+    OriginalGame,
+    logic::general::{
+        board::{HEIGHT, WIDTH},
+        coord::Coord,
+        direction::DIRECTIONS,
+        field::{BasicField, Field, FloodFillField},
+        game_state::GameState,
+        snake::Snake,
+        snakes::SNAKES,
+    },
+};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Estimates where food appears, following the spawning of the standard ruleset:
+/// with less food than `minimumFood` on the board the missing food is placed, otherwise one food
+/// is placed if `100 - rand(100) < foodSpawnChance`. Food is placed uniformly on cells that are
+/// neither occupied nor next to a head.
+///
+/// Only the evaluation uses the model, as expected food in the capture section and as spawn
+/// chance in the health section. The tree search does not branch on spawns, it keeps the food
+/// of the root and plays on without new food.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FoodModel {
+    pub food_spawn_chance: u8,
+    pub minimum_food: u8,
+}
+
+impl Default for FoodModel {
+    fn default() -> Self {
+        Self {
+            food_spawn_chance: 15,
+            minimum_food: 1,
+        }
+    }
+}
+
+impl FoodModel {
+    /// Reads `settings.foodSpawnChance` and `settings.minimumFood`, missing values keep the standard defaults
+    pub fn from_ruleset(ruleset: &HashMap<String, Value>) -> Self {
+        let settings = ruleset.get("settings");
+        let setting = |name: &str| {
+            settings
+                .and_then(|settings| settings.get(name))
+                .and_then(Value::as_u64)
+                .map(|value| value.min(100) as u8)
+        };
+        let default = Self::default();
+        Self {
+            food_spawn_chance: setting("foodSpawnChance").unwrap_or(default.food_spawn_chance),
+            minimum_food: setting("minimumFood").unwrap_or(default.minimum_food),
+        }
+    }
+
+    pub fn from_game(game: &OriginalGame) -> Self {
+        Self::from_ruleset(&game.ruleset)
+    }
+
+    /// Probability that the chance roll places a food, `100 - rand(100)` is never below one
+    pub fn spawn_chance(&self) -> f64 {
+        self.food_spawn_chance.saturating_sub(1) as f64 / 100.0
+    }
+
+    /// Number of food placed after a turn with `food` on the board and its probability
+    pub fn spawn_counts(&self, food: u8) -> Vec<(u8, f64)> {
+        if food < self.minimum_food {
+            vec![(self.minimum_food - food, 1.0)]
+        } else if self.spawn_chance() > 0.0 {
+            vec![(0, 1.0 - self.spawn_chance()), (1, self.spawn_chance())]
+        } else {
+            vec![(0, 1.0)]
+        }
+    }
+
+    /// Expected number of food placed after a turn with `food` on the board
+    pub fn expected_spawns(&self, food: u8) -> f64 {
+        self.spawn_counts(food)
+            .iter()
+            .map(|&(count, probability)| count as f64 * probability)
+            .sum()
+    }
+
+    /// Cells food can be placed on: empty and not next to the head of an alive snake
+    pub fn spawn_cells<F: Field>(state: &GameState<F>) -> Vec<Coord> {
+        let heads: Vec<Coord> = (0..SNAKES as u8)
+            .filter_map(|id| match state.snakes().cell(id).get() {
+                Snake::Alive { head, .. } => Some(head),
+                _ => None,
+            })
+            .collect();
+        let mut cells = Vec::new();
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let coord = Coord::new(x, y);
+                let empty = state.board().cell(x, y).unwrap().get().value() == BasicField::Empty;
+                let next_to_head = heads
+                    .iter()
+                    .any(|&head| DIRECTIONS.iter().any(|&d| head + d == coord));
+                if empty && !next_to_head {
+                    cells.push(coord);
+                }
+            }
+        }
+        cells
+    }
+
+    fn food_count<F: Field>(state: &GameState<F>) -> u8 {
+        let mut food = 0;
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                if state.board().cell(x, y).unwrap().get().value() == BasicField::Food {
+                    food += 1;
+                }
+            }
+        }
+        food
+    }
+
+    /// Probability that food is placed on a given spawn cell after the next turn
+    pub fn spawn_probability<F: Field>(&self, state: &GameState<F>) -> f64 {
+        let cells = Self::spawn_cells(state).len();
+        if cells == 0 {
+            return 0.0;
+        }
+        (self.expected_spawns(Self::food_count(state)) / cells as f64).min(1.0)
+    }
+
+    /// Probability that food was placed on a spawn cell within `turns` turns,
+    /// assuming the food on the board and the free cells stay as they are
+    pub fn spawned_within(spawn_probability: f64, turns: u8) -> f64 {
+        1.0 - (1.0 - spawn_probability).powi(turns as i32)
+    }
+
+    /// Turns in which snake `id` reaches the cells without food it reaches first in a flood fill
+    fn first_reached(flooded: &GameState<FloodFillField>, id: u8) -> Vec<u8> {
+        let mut turns = Vec::new();
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let FloodFillField::Filled {
+                    by,
+                    was_food: false,
+                    ..
+                } = flooded.board().cell(x, y).unwrap().get()
+                else {
+                    continue;
+                };
+                let Some(turn) = by[id as usize] else {
+                    continue;
+                };
+                if by.iter().flatten().any(|&other| other < turn) {
+                    continue;
+                }
+                turns.push(turn);
+            }
+        }
+        turns
+    }
+
+    /// Expected value of food spawning on cells snake `id` reaches first after a flood fill,
+    /// food reached on turn `turn` is worth `5.max(15 - turn)` like existing food
+    pub fn expected_food_score(
+        spawn_probability: f64,
+        flooded: &GameState<FloodFillField>,
+        id: u8,
+    ) -> f64 {
+        Self::first_reached(flooded, id)
+            .into_iter()
+            .map(|turn| {
+                Self::spawned_within(spawn_probability, turn) * 5.max(15 - turn as i32) as f64
+            })
+            .sum()
+    }
+
+    /// Probability that food spawns on a cell snake `id` reaches first, early enough to be
+    /// eaten within `turns` turns
+    pub fn spawn_in_reach(
+        spawn_probability: f64,
+        flooded: &GameState<FloodFillField>,
+        id: u8,
+        turns: u8,
+    ) -> f64 {
+        Self::spawn_in_reach_of(spawn_probability, Self::first_reached(flooded, id), turns)
+    }
+
+    /// Food spawned on a cell before we reach it is still there when we arrive, so every cell
+    /// reached within `turns` has all but the last turn for food to spawn, the last is the move
+    /// onto it
+    fn spawn_in_reach_of(spawn_probability: f64, reached: Vec<u8>, turns: u8) -> f64 {
+        let window = turns.saturating_sub(1);
+        let none = reached
+            .into_iter()
+            .filter(|&turn| turn <= turns)
+            .map(|_| 1.0 - Self::spawned_within(spawn_probability, window))
+            .product::<f64>();
+        1.0 - none
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_game_state;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_ruleset() {
+        let gamestate = read_game_state("requests/failure_1.json");
+        assert_eq!(FoodModel::from_game(&gamestate.game), FoodModel::default());
+
+        let ruleset = HashMap::from([(
+            "settings".to_string(),
+            json!({"foodSpawnChance": 25, "minimumFood": 3}),
+        )]);
+        let model = FoodModel::from_ruleset(&ruleset);
+        assert_eq!(model.food_spawn_chance, 25);
+        assert_eq!(model.minimum_food, 3);
+        assert_eq!(model.spawn_counts(1), vec![(2, 1.0)]);
+        assert_eq!(model.expected_spawns(3), 0.24);
+        assert_eq!(FoodModel::from_ruleset(&HashMap::new()), FoodModel::default());
+    }
+
+    #[test]
+    fn test_spawn_probability() {
+        let gamestate = read_game_state("requests/failure_1.json");
+        let state = GameState::<BasicField>::from(&gamestate);
+        let cells = FoodModel::spawn_cells(&state);
+        assert!(!cells.is_empty());
+
+        let model = FoodModel::default();
+        let expected = model.expected_spawns(FoodModel::food_count(&state)) / cells.len() as f64;
+        assert_eq!(model.spawn_probability(&state), expected);
+
+        let starving = FoodModel {
+            food_spawn_chance: 0,
+            minimum_food: FoodModel::food_count(&state) + 2,
+        };
+        assert_eq!(starving.spawn_probability(&state), 2.0 / cells.len() as f64);
+        assert!(FoodModel::spawned_within(0.01, 10) > FoodModel::spawned_within(0.01, 1));
+    }
+
+    #[test]
+    fn test_spawn_in_reach() {
+        // Food spawning on the far cell while we are still on the way counts as well
+        let near = FoodModel::spawn_in_reach_of(0.01, vec![2], 10);
+        let far = FoodModel::spawn_in_reach_of(0.01, vec![8], 10);
+        assert_eq!(far, FoodModel::spawned_within(0.01, 9));
+        assert_eq!(near, far);
+        assert!(far > FoodModel::spawned_within(0.01, 10 - 8));
+
+        let both = FoodModel::spawn_in_reach_of(0.01, vec![2, 8], 10);
+        assert!(both > near);
+        assert_eq!(FoodModel::spawn_in_reach_of(0.01, vec![11], 10), 0.0);
+    }
+}
@@ -10,7 +10,7 @@ use crate::{
             board::{Board, HEIGHT, WIDTH},
             coord::Coord,
             direction::{DIRECTIONS, Direction},
-            field::{BasicField, Field, FloodFillField},
+            field::{BasicField, BitField, Field, FloodFillField},
             moves::MoveVector,
         },
         legacy::shared::e_snakes::SNAKES,
@@ -23,6 +23,58 @@ use std::{
     hash::{Hash, Hasher},
 };
 
+/// Board writes a single turn can make: a tail, a neck, a head and an eaten food per snake and
+/// the cells of the removed snakes
+const MAX_CHANGES: usize = (WIDTH as usize * HEIGHT as usize) + 4 * SNAKES as usize;
+
+/// What `next_state_with_undo` changed, so `undo` can restore the state before it.
+/// Holds no heap memory, a depth first search can keep one record per level.
+#[derive(Clone)]
+pub struct Undo<F: Field> {
+    /// Previous fields of the written cells, in the order they were written
+    cells: ArrayVec<(Coord, F), MAX_CHANGES>,
+    snakes: [Snake; SNAKES as usize],
+    simulated_turn: u8,
+    eliminated: [bool; SNAKES as usize],
+}
+
+impl<F: Field> Undo<F> {
+    pub fn new() -> Self {
+        Self {
+            cells: ArrayVec::new(),
+            snakes: [Snake::default(); SNAKES as usize],
+            simulated_turn: 0,
+            eliminated: [false; SNAKES as usize],
+        }
+    }
+
+    /// Food that was eaten, including food under the heads of eliminated snakes
+    pub fn eaten_food(&self) -> impl Iterator<Item = Coord> + '_ {
+        self.cells
+            .iter()
+            .filter(|(_, field)| matches!(field.value(), BasicField::Food))
+            .map(|&(coord, _)| coord)
+    }
+
+    /// Snakes that were alive before and are not anymore
+    pub fn eliminated(&self) -> [bool; SNAKES as usize] {
+        self.eliminated
+    }
+
+    /// Snake states before the move
+    pub fn snakes(&self) -> &[Snake; SNAKES as usize] {
+        &self.snakes
+    }
+}
+
+impl<F: Field> Default for Undo<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+type Changes<F> = ArrayVec<(Coord, F), MAX_CHANGES>;
+
 #[derive(Clone)]
 pub struct GameState<T: Field> {
     board: Board<T>,
@@ -78,7 +130,58 @@ impl<F: Field> GameState<F> {
         self.move_tails().move_heads(moves)
     }
 
+    /// Like `next_state`, but records in `undo` what changed so `undo` can restore the state.
+    /// The record is overwritten, so one record can be reused for many moves.
+    pub fn next_state_with_undo(&mut self, moves: Moves, undo: &mut Undo<F>) -> &mut Self {
+        undo.cells.clear();
+        undo.snakes = std::array::from_fn(|id| self.snakes.cell(id as u8).get());
+        undo.simulated_turn = self.simulated_turn;
+        let mut changes = Some(&mut undo.cells);
+        self.move_tails_recorded(&mut changes);
+        self.move_heads_recorded(moves, &mut changes);
+        undo.eliminated = std::array::from_fn(|id| {
+            matches!(undo.snakes[id], Snake::Alive { .. }) && !self.is_alive(id as u8)
+        });
+        self
+    }
+
+    /// Restores the state from before the `next_state_with_undo` that filled `undo`
+    pub fn undo(&mut self, undo: &Undo<F>) -> &mut Self {
+        for &(coord, field) in undo.cells.iter().rev() {
+            self.board.cell_coord(coord).unwrap().set(field);
+        }
+        for (id, snake) in undo.snakes.iter().enumerate() {
+            self.snakes.cell(id as u8).set(*snake);
+        }
+        self.simulated_turn = undo.simulated_turn;
+        self
+    }
+
+    fn set_field(&self, coord: Coord, field: F, changes: &mut Option<&mut Changes<F>>) {
+        let cell = self.board.cell_coord(coord).unwrap();
+        if let Some(changes) = changes {
+            changes.push((coord, cell.get()));
+        }
+        cell.set(field);
+    }
+
+    fn remove_snake(&self, snake: Snake, changes: &mut Option<&mut Changes<F>>) {
+        self.board.remove_snake_with(snake, |coord, field| {
+            if let Some(changes) = changes {
+                changes.push((coord, field));
+            }
+        });
+    }
+
     pub fn move_heads(&mut self, moves: Moves) -> &mut Self {
+        self.move_heads_recorded(moves, &mut None)
+    }
+
+    fn move_heads_recorded(
+        &mut self,
+        moves: Moves,
+        changes: &mut Option<&mut Changes<F>>,
+    ) -> &mut Self {
         // Calculate potential new heads and handle headless snakes and non moves and food and health
         for id in 0..SNAKES {
             let snake = self.snakes.cell(id).get();
@@ -97,14 +200,18 @@ impl<F: Field> GameState<F> {
                     let new_head = head + direction;
                     match self.board.cell(new_head.x, new_head.y) {
                         None => {
-                            self.board.remove_snake(snake);
+                            self.remove_snake(snake, changes);
                             self.snakes.cell(id).set(snake.to_dead()); // Eliminate moved out of bounds directly
                         }
                         Some(field) => {
-                            self.board
-                                .cell(head.x, head.y)
-                                .unwrap()
-                                .set(F::snake(id, Some(direction)));
+                            // A snake of a single part leaves no body behind, its tail follows the head
+                            let old_head = self.board.cell_coord(head).unwrap().get();
+                            let snake = if let BasicField::Empty = old_head.value() {
+                                snake.tail(new_head)
+                            } else {
+                                self.set_field(head, F::snake(id, Some(direction)), changes);
+                                snake
+                            };
                             if let BasicField::Food = field.get().value() {
                                 self.snakes.cell(id).set(
                                     snake
@@ -139,7 +246,7 @@ impl<F: Field> GameState<F> {
             let snake = self.snakes.cell(id).get();
             match snake {
                 Snake::Alive { health, .. } | Snake::Headless { health, .. } if health == 0 => {
-                    self.board.remove_snake(snake);
+                    self.remove_snake(snake, changes);
                     self.snakes.cell(id).set(snake.to_dead());
                 }
                 _ => (),
@@ -212,10 +319,17 @@ impl<F: Field> GameState<F> {
         }
 
         // Remove all snakes that need to be removed
+        // Food under a removed head is eaten nevertheless, feeding happens before eliminations
         for id in 0..SNAKES {
             if let Some(snake) = snakes_to_remove[id as usize] {
+                if let Snake::Alive { head, .. } = snake {
+                    let field = self.board.cell(head.x, head.y).unwrap();
+                    if let BasicField::Food = field.get().value() {
+                        self.set_field(head, F::empty(), changes);
+                    }
+                }
                 self.snakes.cell(id).set(snake.to_dead());
-                self.board.remove_snake(snake);
+                self.remove_snake(snake, changes);
             }
         }
 
@@ -223,10 +337,7 @@ impl<F: Field> GameState<F> {
         for id in 0..SNAKES {
             let snake = self.snakes.cell(id).get();
             if let Snake::Alive { head, .. } = snake {
-                self.board
-                    .cell(head.x, head.y)
-                    .unwrap()
-                    .set(F::snake(id, None));
+                self.set_field(head, F::snake(id, None), changes);
             }
         }
 
@@ -234,6 +345,10 @@ impl<F: Field> GameState<F> {
     }
 
     pub fn move_tails(&mut self) -> &mut Self {
+        self.move_tails_recorded(&mut None)
+    }
+
+    fn move_tails_recorded(&mut self, changes: &mut Option<&mut Changes<F>>) -> &mut Self {
         for id in 0..SNAKES {
             let snake = self.snakes.cell(id).get();
             match snake {
@@ -246,10 +361,13 @@ impl<F: Field> GameState<F> {
                     } = self.board.cell(tail.x, tail.y).unwrap().get().value()
                     {
                         self.snakes.cell(id).set(snake.tail(tail + next));
-                        self.board.cell(tail.x, tail.y).unwrap().set(F::empty());
+                        self.set_field(tail, F::empty(), changes);
+                    } else if let Snake::Alive { .. } = snake {
+                        // Only the head is left, it moves on with the heads
+                        self.set_field(tail, F::empty(), changes);
                     } else {
                         self.snakes.cell(id).set(snake.to_vanished());
-                        self.board.cell(tail.x, tail.y).unwrap().set(F::empty());
+                        self.set_field(tail, F::empty(), changes);
                     }
                 }
                 _ => (),
@@ -726,7 +844,8 @@ impl FloodFillResult {
 
 impl From<GameState<BasicField>> for GameState<FloodFillField> {
     fn from(state: GameState<BasicField>) -> Self {
-        let new_board = Board::default();
+        let mut new_board = Board::default();
+        new_board.set_hazards(state.board.hazards());
         for y in 0..HEIGHT {
             for x in 0..WIDTH {
                 let cell = state.board.cell(x, y).unwrap();
@@ -744,6 +863,37 @@ impl From<GameState<BasicField>> for GameState<FloodFillField> {
     }
 }
 
+impl<F: Field> GameState<F> {
+    /// Copies the state onto a board of another field encoding
+    fn convert<T: Field>(&self, field: impl Fn(F) -> T) -> GameState<T> {
+        let mut new_board = Board::default();
+        new_board.set_hazards(self.board.hazards());
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let cell = self.board.cell(x, y).unwrap();
+                new_board.cell(x, y).unwrap().set(field(cell.get()));
+            }
+        }
+        GameState {
+            board: new_board,
+            snakes: self.snakes.clone(),
+            simulated_turn: self.simulated_turn,
+        }
+    }
+}
+
+impl From<&GameState<BasicField>> for GameState<BitField> {
+    fn from(state: &GameState<BasicField>) -> Self {
+        state.convert(BitField::from)
+    }
+}
+
+impl From<&GameState<BitField>> for GameState<BasicField> {
+    fn from(state: &GameState<BitField>) -> Self {
+        state.convert(|field| field.value())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -751,7 +901,22 @@ mod tests {
 
     #[test]
     fn test_memory_size() {
-        assert_eq!(std::mem::size_of::<GameState<BasicField>>(), 279);
+        assert_eq!(std::mem::size_of::<GameState<BasicField>>(), 295);
+    }
+
+    #[test]
+    fn test_bit_field_conversion() {
+        let gamestate = read_game_state("requests/failure_1.json");
+        let mut state = GameState::<BasicField>::from(&gamestate);
+        let mut packed = GameState::<BitField>::from(&state);
+        assert_eq!(std::mem::size_of::<GameState<BitField>>(), 174);
+        assert_eq!(packed.to_string(), state.to_string());
+
+        // Both encodings play on alike
+        let moves = state.valid_moves().into_iter().next().unwrap();
+        state.next_state(moves);
+        packed.next_state(moves);
+        assert_eq!(GameState::<BasicField>::from(&packed).to_string(), state.to_string());
     }
 
     #[test]
@@ -966,6 +1131,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_next_state_single_part() {
+        let mut gamestate = read_game_state("requests/test_move_request.json");
+        gamestate.board.snakes[0].body.truncate(1);
+        gamestate.board.snakes[0].length = 1;
+        gamestate.you = gamestate.board.snakes[0].clone();
+        gamestate.board.food.push(crate::OriginalCoord { x: 0, y: 3 });
+        let mut state = GameState::<BasicField>::from(&gamestate);
+        let up = [Some(Direction::Up), None, None, None];
+        let parts = |state: &GameState<BasicField>| match state.snakes.cell(0).get() {
+            Snake::Alive {
+                head, tail, length, ..
+            } => (head, tail, length),
+            snake => panic!("Single part snake did not survive: {:?}", snake),
+        };
+
+        // The single part moves on without leaving a body behind
+        state.next_state(up);
+        let (head, tail, length) = parts(&state);
+        assert_eq!((head, tail, length), (Coord::new(0, 2), Coord::new(0, 2), 1));
+        assert!(matches!(state.board.cell(0, 1).unwrap().get(), BasicField::Empty));
+
+        // Eating stacks the new part onto the head, it follows on the next move
+        state.next_state(up);
+        let (head, tail, length) = parts(&state);
+        assert_eq!((head, tail, length), (Coord::new(0, 3), Coord::new(0, 3), 2));
+        assert!(matches!(state.board.cell(0, 2).unwrap().get(), BasicField::Empty));
+        state.next_state(up);
+        let (head, tail, length) = parts(&state);
+        assert_eq!((head, tail, length), (Coord::new(0, 4), Coord::new(0, 3), 2));
+    }
+
     #[test]
     fn test_move_heads() {
         let gamestate = read_game_state("requests/test_move_request.json");
@@ -1084,8 +1281,13 @@ mod tests {
             state.board.cell(9, 0).unwrap().get(),
             BasicField::snake(2, None)
         );
+        // Down to its head, which moves on with the heads
+        assert_eq!(state.board.cell(0, 1).unwrap().get(), BasicField::empty());
         match state.snakes.cell(0).get() {
-            Snake::Vanished { id, .. } => assert_eq!(id, 0),
+            Snake::Alive { tail, head, .. } => {
+                assert_eq!(tail, Coord { x: 0, y: 1 });
+                assert_eq!(head, Coord { x: 0, y: 1 });
+            }
             _ => panic!("Problem with Snake A"),
         }
         match state.snakes.cell(2).get() {
@@ -1199,6 +1401,64 @@ mod tests {
         assert!(matches!(state.snakes.cell(3).get(), Snake::Dead { .. }));
     }
 
+    fn assert_same(state: &GameState<BasicField>, expected: &GameState<BasicField>) {
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                assert_eq!(
+                    state.board.cell(x, y).unwrap().get(),
+                    expected.board.cell(x, y).unwrap().get()
+                );
+            }
+        }
+        for id in 0..SNAKES {
+            assert_eq!(state.snakes.cell(id).get(), expected.snakes.cell(id).get());
+        }
+        assert_eq!(state.simulated_turn, expected.simulated_turn);
+    }
+
+    /// Walks all moves `depth` turns deep in place, checking every move against a cloned state
+    fn walk_with_undo(state: &mut GameState<BasicField>, depth: u8, counts: &mut (usize, usize)) {
+        if depth == 0 {
+            return;
+        }
+        let before = state.clone();
+        let mut undo = Undo::new();
+        for moves in before.valid_moves() {
+            let mut expected = before.clone();
+            expected.next_state(moves);
+            state.next_state_with_undo(moves, &mut undo);
+            assert_same(state, &expected);
+            for food in undo.eaten_food() {
+                assert_eq!(before.board.cell_coord(food).unwrap().get(), BasicField::Food);
+                counts.0 += 1;
+            }
+            for id in 0..SNAKES {
+                let eliminated = before.is_alive(id) && !expected.is_alive(id);
+                assert_eq!(undo.eliminated()[id as usize], eliminated);
+                counts.1 += eliminated as usize;
+            }
+            walk_with_undo(state, depth - 1, counts);
+            state.undo(&undo);
+            assert_same(state, &before);
+        }
+    }
+
+    #[test]
+    fn test_next_state_with_undo() {
+        let mut counts = (0, 0);
+        for file in [
+            "requests/test_move_request.json",
+            "requests/test_3_head_collision.json",
+            "requests/failure_1.json",
+            "requests/failure_27_grab_food.json",
+        ] {
+            let mut state = GameState::<BasicField>::from(&read_game_state(file));
+            walk_with_undo(&mut state, 2, &mut counts);
+        }
+        // The walks ate food and eliminated snakes
+        assert!(counts.0 > 0 && counts.1 > 0, "{:?}", counts);
+    }
+
     #[test]
     fn test_quick_hash() {
         let gamestate = read_game_state("requests/test_move_request_2c.json");
@@ -1322,6 +1582,25 @@ mod benchmarks {
         });
     }
 
+    #[bench]
+    fn bench_next_state_with_undo(b: &mut test::Bencher) {
+        let gamestate = read_game_state("requests/test_move_request.json");
+        let mut state = GameState::<BasicField>::from(&gamestate);
+        let mut undo = Undo::new();
+        b.iter(|| {
+            black_box(&mut state).next_state_with_undo(
+                black_box([
+                    Some(Direction::Up),
+                    Some(Direction::Left),
+                    Some(Direction::Down),
+                    Some(Direction::Down),
+                ]),
+                &mut undo,
+            );
+            state.undo(&undo);
+        });
+    }
+
     #[bench]
     fn bench_local_environment_hash(b: &mut test::Bencher) {
         let gamestate = read_game_state("requests/test_move_request.json");
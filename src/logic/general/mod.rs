@@ -2,8 +2,10 @@ pub mod board;
 pub mod coord;
 pub mod direction;
 pub mod field;
+pub mod food_model;
 pub mod game_state;
 pub mod moves;
 pub mod snake;
 pub mod snakes;
 pub mod evaluation;
+pub mod evaluation_report;
@@ -17,17 +17,39 @@ impl Snakes {
             Cell::new(Snake::default()),
             Cell::new(Snake::default()),
         ];
+        for (id, snake) in Self::slots(board, you) {
+            d_snakes[id] = Cell::new(Snake::from_request(snake, id as u8));
+        }
+        Snakes { snakes: d_snakes }
+    }
+
+    /// Original snake ids in the slot order that `from_request` assigns them.
+    pub fn request_ids<'a>(
+        board: &'a OriginalBoard,
+        you: &OriginalBattlesnake,
+    ) -> [Option<&'a str>; SNAKES] {
+        let mut ids = [None; SNAKES];
+        for (id, snake) in Self::slots(board, you) {
+            ids[id] = Some(snake.id.as_str());
+        }
+        ids
+    }
+
+    /// Snakes of a request with their slot, ours in slot 0 and the others in request order
+    fn slots<'a>(
+        board: &'a OriginalBoard,
+        you: &OriginalBattlesnake,
+    ) -> impl Iterator<Item = (usize, &'a OriginalBattlesnake)> {
         let mut snake_id = 0;
-        for snake in board.snakes.iter() {
+        board.snakes.iter().map(move |snake| {
             let id = if snake.id == you.id {
                 0
             } else {
                 snake_id += 1;
                 snake_id
             };
-            d_snakes[id] = Cell::new(Snake::from_request(snake, id as u8));
-        }
-        Snakes { snakes: d_snakes }
+            (id, snake)
+        })
     }
 
     pub fn from_cells(snakes: [Cell<Snake>; SNAKES]) -> Self {
@@ -109,4 +131,21 @@ mod tests {
         assert_eq!(d_snakes.cell(2).get(), Snake::NonExistent);
         assert_eq!(d_snakes.cell(3).get(), Snake::NonExistent);
     }
+
+    #[test]
+    fn test_request_ids() {
+        let gamestate = crate::read_game_state("requests/test_move_request.json");
+        let ids = Snakes::request_ids(&gamestate.board, &gamestate.you);
+        assert_eq!(ids[0], Some(gamestate.you.id.as_str()));
+        let others: Vec<&str> = gamestate
+            .board
+            .snakes
+            .iter()
+            .filter(|snake| snake.id != gamestate.you.id)
+            .map(|snake| snake.id.as_str())
+            .collect();
+        for (slot, id) in others.iter().enumerate() {
+            assert_eq!(ids[slot + 1], Some(*id));
+        }
+    }
 }
@@ -1,5 +1,6 @@
 use crate::{
     logic::{
+        config,
         legacy::shared::{
             e_board::EField, e_coord::ECoord, e_direction::EDIRECTION_VECTORS,
             e_game_state::EGameState, e_snakes::SNAKES,
@@ -10,7 +11,7 @@ use crate::{
 };
 use e_score_board::EScoreBoard;
 use e_state_tree::EStateTree;
-use std::{env, time::Duration};
+use std::time::Duration;
 
 use super::shared::e_scores::Scores;
 
@@ -179,7 +180,7 @@ impl BreadthFirstSnake {
             ],
             true,
         );
-        if env::var("MODE").unwrap_or("".to_string()) == "test" {
+        if config::param("MODE").is_some_and(|mode| mode == "test") {
             println!("{}", &board_weights);
         }
         for d in 0..4 {
@@ -206,7 +207,7 @@ impl BreadthFirstSnake {
                 true,
             );
         }
-        if env::var("MODE").unwrap_or("".to_string()) == "test" {
+        if config::param("MODE").is_some_and(|mode| mode == "test") {
             println!("{}", &board_weights_far);
         }
         for d in 0..4 {
@@ -1,5 +1,46 @@
-use crate::{OriginalDirection, OriginalGameState};
+use std::time::Duration;
+
+use crate::{
+    OriginalDirection, OriginalGameState,
+    logic::general::{evaluation::Reason, evaluation_report::EvaluationReport},
+};
+
+/// Size and duration of the search a brain did for its decision
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchSummary {
+    pub total_nodes: usize,
+    pub max_depth_reached: u16,
+    /// Bytes held by the search tree, zero if the brain does not measure it
+    pub memory_bytes: usize,
+    pub duration: Duration,
+}
+
+/// A move together with the information that led to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decision {
+    pub direction: OriginalDirection,
+    pub reason: Option<Reason>,
+    pub search: Option<SearchSummary>,
+    /// Full evaluation of brains that use `Evaluation`
+    pub evaluation: Option<EvaluationReport>,
+}
+
+impl Decision {
+    pub fn new(direction: OriginalDirection) -> Self {
+        Self {
+            direction,
+            reason: None,
+            search: None,
+            evaluation: None,
+        }
+    }
+}
 
 pub trait Brain {
     fn logic(&self, gamestate: &OriginalGameState) -> OriginalDirection;
+
+    /// Brains that evaluate their moves report why they picked them
+    fn decide(&self, gamestate: &OriginalGameState) -> Decision {
+        Decision::new(self.logic(gamestate))
+    }
 }
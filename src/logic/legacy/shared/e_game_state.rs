@@ -387,6 +387,20 @@ impl EGameState {
     /// tails and hunger eliminations should be done before
     /// responsible for handling growth by stacking (already new) tail
     fn move_heads(&mut self, moveset: &[Option<EDirection>; SNAKES as usize]) -> Result<()> {
+        // Out of bounds snakes are eliminated before collisions, their bodies do not count
+        for i in 0..SNAKES {
+            if let Some(direction) = moveset[i as usize]
+                && let Some(snake) = self.snakes.get_mut(i).as_mut()
+            {
+                let new_head = snake.head.move_in_direction(direction);
+                if self.board.get(new_head.x, new_head.y).is_none() {
+                    snake._die = true;
+                }
+            }
+        }
+        self.eliminate_dead_snakes()?;
+
+        let mut new_heads = [None; SNAKES as usize];
         for i in 0..SNAKES {
             if let Some(direction) = moveset[i as usize] {
                 if let Some(snake) = self.snakes.get_mut(i).as_mut() {
@@ -464,12 +478,23 @@ impl EGameState {
                         _ => panic!("Invalid state while moving heads"),
                     }
                     snake.head = new_head;
+                    new_heads[i as usize] = Some(new_head);
                 }
             }
         }
 
         self.eliminate_dead_snakes()?;
 
+        // Fields contested only by eliminated snakes are left empty, food is eaten nevertheless
+        for new_head in new_heads.iter().flatten() {
+            if let Some(EField::Contested { snake_number, .. }) =
+                self.board.get(new_head.x, new_head.y)
+                && self.snakes.get(snake_number).is_none()
+            {
+                self.board.set(new_head.x, new_head.y, EField::Empty);
+            }
+        }
+
         for i in 0..SNAKES {
             if let Some(snake) = self.snakes.get_mut(i).as_mut() {
                 if !snake._far_away {
@@ -1,9 +1,9 @@
 use core::fmt;
-use std::{env, fmt::Display};
+use std::fmt::Display;
 
 use log::info;
 
-use crate::OriginalGame;
+use crate::{OriginalGame, logic::config};
 
 use super::e_direction::EDirection;
 
@@ -59,7 +59,7 @@ impl Scores {
             result.to_direction()
         ));
         s.push_str(&format!("{}", self));
-        if env::var("MODE").unwrap_or("".to_string()) == "test" {
+        if config::param("MODE").is_some_and(|mode| mode == "test") {
             println!("{}", s);
         } else {
             info!("{}", s);
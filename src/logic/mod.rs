@@ -10,19 +10,21 @@
 // To get you started we've included code to prevent your Battlesnake from moving backwards.
 // For more info see docs.battlesnake.com
 
-use legacy::shared::brain::Brain;
+use legacy::shared::brain::{Brain, Decision};
 use log::info;
-use serde_json::{Value, json};
-use std::env;
+use serde_json::Value;
 
 use crate::{
     OriginalBattlesnake, OriginalBoard, OriginalDirection, OriginalGame, OriginalGameState,
 };
 
+pub mod config;
 pub mod depth_first;
 pub mod general;
 pub mod legacy;
+pub mod opponents;
 pub mod single_gamestate_nodes;
+pub mod watchdog;
 
 // info is called when you create your Battlesnake on play.battlesnake.com
 // and controls your Battlesnake's appearance
@@ -30,17 +32,7 @@ pub mod single_gamestate_nodes;
 pub fn info() -> Value {
     info!("INFO");
 
-    let color = env::var("SNAKE_COLOR").unwrap_or("#f5982f".to_string());
-    let head = env::var("SNAKE_HEAD").unwrap_or("chicken".to_string());
-    let tail = env::var("SNAKE_TAIL").unwrap_or("duck".to_string());
-
-    json!({
-        "apiversion": "1",
-        "author": "WbaN",
-        "color": color,
-        "head": head,
-        "tail": tail,
-    })
+    config::SnakeConfig::from_env().info()
 }
 
 // start is called when your Battlesnake begins a game
@@ -58,10 +50,24 @@ pub fn end(_game: &OriginalGame, _turn: &i32, _board: &OriginalBoard, _you: &Ori
     info!("GAME OVER");
 }
 
+/// All variant names accepted by `get_move`
+pub const VARIANTS: [&str; 5] = [
+    "simple_hungry",
+    "simple_tree_search",
+    "breadth_first",
+    "depth_first",
+    "single_gamestate_nodes",
+];
+
 // move is called on every turn and returns your next move
 // Valid moves are Move::Up, Move::Down, Move::Left, or Move::Right
 // See https://docs.battlesnake.com/api/example-move for available data
 pub fn get_move(gamestate: &OriginalGameState, variant: String) -> OriginalDirection {
+    decide(gamestate, variant).direction
+}
+
+/// Like `get_move`, with the reason and search size of the decision if the variant reports them
+pub fn decide(gamestate: &OriginalGameState, variant: String) -> Decision {
     let brain: Box<dyn Brain> = match variant.as_str() {
         "simple_hungry" => Box::new(legacy::simple_hungry::SimpleHungrySnake::new()),
         "simple_tree_search" => Box::new(legacy::simple_tree_search::SimpleTreeSearchSnake::new()),
@@ -72,7 +78,7 @@ pub fn get_move(gamestate: &OriginalGameState, variant: String) -> OriginalDirec
         }
         _ => panic!("No VARIANT given for snake"),
     };
-    brain.logic(gamestate)
+    brain.decide(gamestate)
 }
 
 pub fn get_move_with_evaluation(gamestate: &OriginalGameState) -> (OriginalDirection, String) {
@@ -0,0 +1,496 @@
+use crate::{
+    OriginalBattlesnake, OriginalBoard, OriginalCoord, OriginalGameState,
+    logic::general::snakes::{SNAKES, Snakes},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    io::ErrorKind,
+    sync::{LazyLock, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// Moves observed before an opponent is judged by its statistics
+const MIN_MOVES: u32 = 50;
+/// An opponent that takes fewer head-to-heads it would lose or draw is trusted to avoid them
+const MAX_OPTIMISTIC_HEAD_TO_HEAD: f64 = 0.2;
+/// An opponent that moves toward other heads less often is trusted not to hunt
+const MAX_OPTIMISTIC_AGGRESSION: f64 = 0.5;
+/// Games without a request for this long are forgotten, their /end is not coming anymore
+const ABANDONED_AFTER: Duration = Duration::from_secs(600);
+
+/// How the search treats the moves of an opponent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Stance {
+    /// Every move of the opponent is considered
+    #[default]
+    Pessimistic,
+    /// The opponent is assumed to avoid head-to-heads it would not win
+    Optimistic,
+}
+
+/// Behaviour of an opponent, counted per observed move
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct OpponentStats {
+    pub games: u32,
+    /// Games the opponent ended as the last snake alive
+    pub wins: u32,
+    /// Games against the opponent that we ended as the last snake alive
+    pub our_wins: u32,
+    pub moves: u32,
+    /// Moves that got closer to the nearest other head
+    pub toward_head: u32,
+    /// Moves that got closer to the nearest food, out of the moves with food on the board
+    pub toward_food: u32,
+    pub food_opportunities: u32,
+    /// Moves that ended on the outermost ring of the board
+    pub on_wall: u32,
+    /// Moves next to a shorter snake's head, out of the moves where one was reachable
+    pub head_to_head_taken_longer: u32,
+    pub head_to_head_opportunities_longer: u32,
+    /// Moves next to the head of a snake at least as long, out of the moves where one was reachable
+    pub head_to_head_taken_not_longer: u32,
+    pub head_to_head_opportunities_not_longer: u32,
+    pub shouts: u32,
+    pub last_shout: Option<String>,
+}
+
+impl OpponentStats {
+    fn rate(count: u32, total: u32) -> Option<f64> {
+        (total > 0).then(|| count as f64 / total as f64)
+    }
+
+    pub fn aggression(&self) -> Option<f64> {
+        Self::rate(self.toward_head, self.moves)
+    }
+
+    pub fn food_greed(&self) -> Option<f64> {
+        Self::rate(self.toward_food, self.food_opportunities)
+    }
+
+    pub fn wall_hugging(&self) -> Option<f64> {
+        Self::rate(self.on_wall, self.moves)
+    }
+
+    pub fn head_to_head_when_longer(&self) -> Option<f64> {
+        Self::rate(
+            self.head_to_head_taken_longer,
+            self.head_to_head_opportunities_longer,
+        )
+    }
+
+    pub fn head_to_head_when_not_longer(&self) -> Option<f64> {
+        Self::rate(
+            self.head_to_head_taken_not_longer,
+            self.head_to_head_opportunities_not_longer,
+        )
+    }
+
+    /// Opponents are treated pessimistically until enough of their moves were seen. The optimistic
+    /// search assumes they avoid the head-to-heads they would lose or draw, so that is what counts.
+    pub fn stance(&self) -> Stance {
+        if self.moves < MIN_MOVES {
+            return Stance::Pessimistic;
+        }
+        let head_to_head = self.head_to_head_when_not_longer().unwrap_or(1.0);
+        let aggression = self.aggression().unwrap_or(1.0);
+        if head_to_head < MAX_OPTIMISTIC_HEAD_TO_HEAD && aggression < MAX_OPTIMISTIC_AGGRESSION {
+            Stance::Optimistic
+        } else {
+            Stance::Pessimistic
+        }
+    }
+
+    pub fn merge(&mut self, other: &OpponentStats) {
+        self.games += other.games;
+        self.wins += other.wins;
+        self.our_wins += other.our_wins;
+        self.moves += other.moves;
+        self.toward_head += other.toward_head;
+        self.toward_food += other.toward_food;
+        self.food_opportunities += other.food_opportunities;
+        self.on_wall += other.on_wall;
+        self.head_to_head_taken_longer += other.head_to_head_taken_longer;
+        self.head_to_head_opportunities_longer += other.head_to_head_opportunities_longer;
+        self.head_to_head_taken_not_longer += other.head_to_head_taken_not_longer;
+        self.head_to_head_opportunities_not_longer += other.head_to_head_opportunities_not_longer;
+        self.shouts += other.shouts;
+        if other.last_shout.is_some() {
+            self.last_shout = other.last_shout.clone();
+        }
+    }
+
+    /// Counts the move of `before` to `after` with the board as it was before the move
+    fn observe(&mut self, board: &OriginalBoard, before: &OriginalBattlesnake, after: &OriginalBattlesnake) {
+        if distance(&before.head, &after.head) != 1 {
+            return;
+        }
+        self.moves += 1;
+
+        let others = board.snakes.iter().filter(|s| s.id != before.id);
+        if let Some(target) = others.clone().map(|s| &s.head).min_by_key(|h| distance(&before.head, h))
+            && distance(&after.head, target) < distance(&before.head, target)
+        {
+            self.toward_head += 1;
+        }
+
+        if let Some(food) = board.food.iter().min_by_key(|f| distance(&before.head, f)) {
+            self.food_opportunities += 1;
+            if distance(&after.head, food) < distance(&before.head, food) {
+                self.toward_food += 1;
+            }
+        }
+
+        let (width, height) = (board.width, board.height as i32);
+        if after.head.x == 0 || after.head.y == 0 || after.head.x == width - 1 || after.head.y == height - 1 {
+            self.on_wall += 1;
+        }
+
+        let (shorter, not_shorter): (Vec<&OriginalBattlesnake>, Vec<&OriginalBattlesnake>) = others
+            .filter(|s| distance(&before.head, &s.head) == 2)
+            .partition(|s| s.length < before.length);
+        if !shorter.is_empty() {
+            self.head_to_head_opportunities_longer += 1;
+            if shorter.iter().any(|s| distance(&after.head, &s.head) == 1) {
+                self.head_to_head_taken_longer += 1;
+            }
+        }
+        if !not_shorter.is_empty() {
+            self.head_to_head_opportunities_not_longer += 1;
+            if not_shorter.iter().any(|s| distance(&after.head, &s.head) == 1) {
+                self.head_to_head_taken_not_longer += 1;
+            }
+        }
+
+        if let Some(shout) = after.shout.as_ref().filter(|s| !s.is_empty()) {
+            self.shouts += 1;
+            self.last_shout = Some(shout.clone());
+        }
+    }
+}
+
+fn distance(a: &OriginalCoord, b: &OriginalCoord) -> i32 {
+    (a.x - b.x).abs() + (a.y - b.y).abs()
+}
+
+struct GameRecord {
+    turn: i32,
+    board: OriginalBoard,
+    stats: HashMap<String, OpponentStats>,
+    /// Ids of every snake of ours that received a request in the game
+    ours: HashSet<String>,
+    updated: Instant,
+}
+
+impl GameRecord {
+    fn new(gamestate: &OriginalGameState) -> Self {
+        let stats = gamestate
+            .board
+            .snakes
+            .iter()
+            .filter(|s| s.id != gamestate.you.id)
+            .map(|s| {
+                let stats = OpponentStats {
+                    games: 1,
+                    ..Default::default()
+                };
+                (s.name.clone(), stats)
+            })
+            .collect();
+        Self {
+            turn: gamestate.turn,
+            board: gamestate.board.clone(),
+            stats,
+            ours: HashSet::from([gamestate.you.id.clone()]),
+            updated: Instant::now(),
+        }
+    }
+
+    /// Counts the moves the opponents made since the previous observed turn into the record
+    /// and `by_name`
+    fn update(&mut self, gamestate: &OriginalGameState, by_name: &mut HashMap<String, OpponentStats>) {
+        self.updated = Instant::now();
+        if self.ours.insert(gamestate.you.id.clone()) {
+            // Another snake of ours in the same game is no opponent
+            self.stats.remove(&gamestate.you.name);
+        }
+        if gamestate.turn <= self.turn {
+            return;
+        }
+        for before in self.board.snakes.iter().filter(|s| !self.ours.contains(&s.id)) {
+            let Some(after) = gamestate.board.snakes.iter().find(|s| s.id == before.id) else {
+                continue;
+            };
+            let mut observed = OpponentStats::default();
+            observed.observe(&self.board, before, after);
+            self.stats.entry(before.name.clone()).or_default().merge(&observed);
+            by_name.entry(before.name.clone()).or_default().merge(&observed);
+        }
+        self.turn = gamestate.turn;
+        self.board = gamestate.board.clone();
+    }
+}
+
+/// Opponent statistics per game and per snake name across games
+#[derive(Default)]
+pub struct OpponentProfiles {
+    games: HashMap<String, GameRecord>,
+    by_name: HashMap<String, OpponentStats>,
+}
+
+static PROFILES: LazyLock<Mutex<OpponentProfiles>> = LazyLock::new(Mutex::default);
+
+/// Profiles shared by the server and the brains
+pub fn profiles() -> MutexGuard<'static, OpponentProfiles> {
+    PROFILES.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl OpponentProfiles {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts the moves the opponents made since the previous observed turn of the game.
+    /// Repeated or older turns, e.g. from a second snake of ours in the same game, are ignored.
+    pub fn observe(&mut self, gamestate: &OriginalGameState) {
+        self.forget_abandoned(Instant::now());
+        match self.games.get_mut(&gamestate.game.id) {
+            Some(record) => record.update(gamestate, &mut self.by_name),
+            None => {
+                self.games
+                    .insert(gamestate.game.id.clone(), GameRecord::new(gamestate));
+            }
+        }
+    }
+
+    /// Counts the last moves and the outcome of the game, then forgets the game and returns
+    /// the statistics gathered in it. A game that is not recorded, e.g. ended already for
+    /// another snake of ours, returns no statistics.
+    pub fn end_game(&mut self, gamestate: &OriginalGameState) -> HashMap<String, OpponentStats> {
+        let Some(mut record) = self.games.remove(&gamestate.game.id) else {
+            return HashMap::new();
+        };
+        record.update(gamestate, &mut self.by_name);
+        let winner = match gamestate.board.snakes.as_slice() {
+            [winner] => Some(winner),
+            _ => None,
+        };
+        for (name, stats) in record.stats.iter_mut() {
+            let outcome = OpponentStats {
+                wins: winner.is_some_and(|w| !record.ours.contains(&w.id) && &w.name == name) as u32,
+                our_wins: winner.is_some_and(|w| record.ours.contains(&w.id)) as u32,
+                ..Default::default()
+            };
+            stats.merge(&outcome);
+            let finished = OpponentStats {
+                games: 1,
+                ..outcome
+            };
+            self.by_name.entry(name.clone()).or_default().merge(&finished);
+        }
+        record.stats
+    }
+
+    /// Forgets the games without a request since `ABANDONED_AFTER` before `now`, their moves
+    /// stay counted by name
+    fn forget_abandoned(&mut self, now: Instant) {
+        let by_name = &mut self.by_name;
+        self.games.retain(|_, record| {
+            if now.duration_since(record.updated) < ABANDONED_AFTER {
+                return true;
+            }
+            for name in record.stats.keys() {
+                by_name.entry(name.clone()).or_default().games += 1;
+            }
+            false
+        });
+    }
+
+    /// Loads the statistics by name of earlier runs, a missing file starts an empty database
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::new()),
+            Err(e) => return Err(format!("Cannot read {}: {}", path, e)),
+        };
+        let by_name: HashMap<String, OpponentStats> =
+            serde_json::from_str(&content).map_err(|e| format!("Cannot parse {}: {}", path, e))?;
+        Ok(Self {
+            games: HashMap::new(),
+            by_name,
+        })
+    }
+
+    /// Writes the statistics by name sorted by name, the file is replaced only once fully written
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let sorted: BTreeMap<&String, &OpponentStats> = self.by_name.iter().collect();
+        let content = serde_json::to_string_pretty(&sorted).map_err(|e| e.to_string())?;
+        let temporary = format!("{}.tmp", path);
+        fs::write(&temporary, content + "\n")
+            .map_err(|e| format!("Cannot write {}: {}", temporary, e))?;
+        fs::rename(&temporary, path).map_err(|e| format!("Cannot write {}: {}", path, e))
+    }
+
+    /// Keeps the games in progress and replaces the statistics by name
+    pub fn replace_by_name(&mut self, other: OpponentProfiles) {
+        self.by_name = other.by_name;
+    }
+
+    pub fn in_game(&self, game_id: &str, name: &str) -> Option<&OpponentStats> {
+        self.games.get(game_id)?.stats.get(name)
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&OpponentStats> {
+        self.by_name.get(name)
+    }
+
+    /// Stances in the slot order of `GameState`, our own slot is always pessimistic
+    pub fn stances(&self, gamestate: &OriginalGameState) -> [Stance; SNAKES] {
+        let ids = Snakes::request_ids(&gamestate.board, &gamestate.you);
+        std::array::from_fn(|slot| {
+            let Some(id) = ids[slot].filter(|_| slot > 0) else {
+                return Stance::Pessimistic;
+            };
+            gamestate
+                .board
+                .snakes
+                .iter()
+                .find(|s| s.id == id)
+                .and_then(|s| self.by_name(&s.name))
+                .map_or(Stance::Pessimistic, OpponentStats::stance)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_game_state;
+
+    fn step(gamestate: &OriginalGameState, name: &str, dx: i32, dy: i32) -> OriginalGameState {
+        let mut next: OriginalGameState =
+            serde_json::from_value(serde_json::to_value(gamestate).unwrap()).unwrap();
+        next.turn += 1;
+        let snake = next.board.snakes.iter_mut().find(|s| s.name == name).unwrap();
+        snake.head.x += dx;
+        snake.head.y += dy;
+        snake.body.insert(0, snake.head);
+        snake.body.pop();
+        next
+    }
+
+    #[test]
+    fn test_observe() {
+        let gamestate = read_game_state("requests/failure_1.json");
+        let opponent = gamestate
+            .board
+            .snakes
+            .iter()
+            .find(|s| s.id != gamestate.you.id)
+            .unwrap()
+            .name
+            .clone();
+
+        let mut profiles = OpponentProfiles::new();
+        profiles.observe(&gamestate);
+        let next = step(&gamestate, &opponent, 1, 0);
+        profiles.observe(&next);
+        // Repeated turns are not counted twice
+        profiles.observe(&next);
+
+        let stats = profiles.in_game(&gamestate.game.id, &opponent).unwrap();
+        assert_eq!(stats.games, 1);
+        assert_eq!(stats.moves, 1);
+        assert_eq!(profiles.by_name(&opponent).unwrap().moves, 1);
+        assert_eq!(profiles.stances(&next), [Stance::Pessimistic; SNAKES]);
+
+        let ended = profiles.end_game(&next);
+        assert_eq!(ended[&opponent].moves, 1);
+        assert!(profiles.in_game(&gamestate.game.id, &opponent).is_none());
+        assert_eq!(profiles.by_name(&opponent).unwrap().games, 1);
+    }
+
+    #[test]
+    fn test_own_snakes_and_abandoned_games() {
+        let gamestate = read_game_state("requests/failure_1.json");
+        let mut snakes = gamestate.board.snakes.iter().filter(|s| s.id != gamestate.you.id);
+        let (ours, opponent) = (snakes.next().unwrap().clone(), snakes.next().unwrap().name.clone());
+        let mut second = step(&gamestate, &ours.name, 0, 0);
+        second.turn = gamestate.turn;
+        second.you = ours.clone();
+
+        let mut profiles = OpponentProfiles::new();
+        profiles.observe(&gamestate);
+        profiles.observe(&second);
+        assert!(profiles.in_game(&gamestate.game.id, &ours.name).is_none());
+        let next = step(&gamestate, &ours.name, 1, 0);
+        profiles.observe(&next);
+        assert!(profiles.by_name(&ours.name).is_none());
+        assert!(profiles.in_game(&gamestate.game.id, &opponent).is_some());
+
+        // The /end of the second snake of ours does not record the game again
+        profiles.end_game(&next);
+        assert!(profiles.end_game(&second).is_empty());
+        assert!(profiles.in_game(&gamestate.game.id, &opponent).is_none());
+        assert_eq!(profiles.by_name(&opponent).unwrap().games, 1);
+
+        // A game without /end is forgotten, its opponents still count the game
+        profiles.observe(&gamestate);
+        profiles.forget_abandoned(Instant::now() + ABANDONED_AFTER);
+        assert!(profiles.in_game(&gamestate.game.id, &opponent).is_none());
+        assert_eq!(profiles.by_name(&opponent).unwrap().games, 2);
+    }
+
+    #[test]
+    fn test_stance() {
+        // Avoiding head-to-heads when longer says nothing about the ones it would lose or draw
+        let mut stats = OpponentStats {
+            moves: MIN_MOVES,
+            toward_head: 10,
+            head_to_head_taken_longer: 0,
+            head_to_head_opportunities_longer: 5,
+            ..Default::default()
+        };
+        assert_eq!(stats.stance(), Stance::Pessimistic);
+        stats.head_to_head_opportunities_not_longer = 5;
+        assert_eq!(stats.stance(), Stance::Optimistic);
+        stats.head_to_head_taken_not_longer = 4;
+        assert_eq!(stats.stance(), Stance::Pessimistic);
+        stats.moves = MIN_MOVES - 1;
+        stats.head_to_head_taken_not_longer = 0;
+        assert_eq!(stats.stance(), Stance::Pessimistic);
+    }
+
+    #[test]
+    fn test_end_game_and_persistence() {
+        let mut gamestate = read_game_state("requests/failure_1.json");
+        let you = gamestate.you.id.clone();
+        let opponent = gamestate
+            .board
+            .snakes
+            .iter()
+            .find(|s| s.id != you)
+            .unwrap()
+            .name
+            .clone();
+
+        let mut profiles = OpponentProfiles::new();
+        profiles.observe(&gamestate);
+        gamestate.turn += 1;
+        gamestate.board.snakes.retain(|s| s.id == you);
+        let ended = profiles.end_game(&gamestate);
+        assert_eq!(ended[&opponent].our_wins, 1);
+        assert_eq!(ended[&opponent].wins, 0);
+
+        let path = std::env::temp_dir().join(format!("opponents_{}.json", std::process::id()));
+        let path = path.to_string_lossy();
+        profiles.save(&path).unwrap();
+        let loaded = OpponentProfiles::load(&path).unwrap();
+        assert_eq!(loaded.by_name(&opponent), profiles.by_name(&opponent));
+        fs::remove_file(path.as_ref()).unwrap();
+
+        assert!(OpponentProfiles::load(&path).unwrap().by_name.is_empty());
+    }
+}
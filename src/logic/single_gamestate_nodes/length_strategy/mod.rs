@@ -0,0 +1,150 @@
+use crate::logic::{
+    config,
+    general::{
+        direction::Direction,
+        evaluation::Evaluation,
+        field::{BasicField, FloodFillField},
+        game_state::{FloodFillResult, GameState},
+        snake::Snake,
+        snakes::SNAKES,
+    },
+};
+
+/// Plans to stay strictly longer than nearby opponents so head-to-heads are won,
+/// without growing into a body that no longer fits the space we control
+pub struct LengthStrategy {
+    /// Lead over every opponent we want to keep, `LENGTH_LEAD`
+    pub lead: u8,
+    /// Head distance up to which an opponent counts as nearby, `LENGTH_NEARBY_DISTANCE`
+    pub nearby_distance: u8,
+    /// Flooded area per length below which growing is penalized, `LENGTH_SPACE_FACTOR`
+    pub space_factor: u8,
+}
+
+impl Default for LengthStrategy {
+    fn default() -> Self {
+        Self {
+            lead: 2,
+            nearby_distance: 4,
+            space_factor: 2,
+        }
+    }
+}
+
+impl LengthStrategy {
+    /// Default thresholds overridden by the parameters of the snake
+    pub fn read() -> Self {
+        let default = Self::default();
+        let param = |name: &str, default: u8| {
+            config::param(name)
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+        Self {
+            lead: param("LENGTH_LEAD", default.lead),
+            nearby_distance: param("LENGTH_NEARBY_DISTANCE", default.nearby_distance),
+            space_factor: param("LENGTH_SPACE_FACTOR", default.space_factor),
+        }
+    }
+
+    /// Our length minus the length of each alive opponent, `None` for ourself and dead snakes
+    pub fn leads(gamestate: &GameState<BasicField>) -> [Option<i16>; SNAKES] {
+        let lengths = gamestate.snakes().lengths();
+        std::array::from_fn(|id| match gamestate.snakes().cell(id as u8).get() {
+            Snake::Alive { .. } if id != 0 => Some(lengths[0] as i16 - lengths[id] as i16),
+            _ => None,
+        })
+    }
+
+    /// Adds the "Length" section, `floods` are the flood fills of the capture section per direction
+    pub fn evaluate(
+        &self,
+        gamestate: &GameState<BasicField>,
+        floods: &[(Direction, GameState<FloodFillField>, FloodFillResult)],
+        evaluation: &mut Evaluation,
+    ) {
+        evaluation.new_section("Length");
+        let Snake::Alive { head, length, .. } = gamestate.snakes().cell(0).get() else {
+            return;
+        };
+        let leads = Self::leads(gamestate);
+        let Some(smallest_lead) = leads.iter().flatten().min().copied() else {
+            return;
+        };
+        let wants_to_grow = smallest_lead < self.lead as i16;
+
+        for (direction, _, result) in floods {
+            let new_head = head + *direction;
+            let eats =
+                gamestate.board().cell_coord(new_head).map(|f| f.get()) == Some(BasicField::Food);
+            let growth = eats as i16;
+
+            for (id, lead) in leads.iter().enumerate() {
+                let Some(lead) = lead else {
+                    continue;
+                };
+                let Snake::Alive {
+                    head: opponent_head,
+                    ..
+                } = gamestate.snakes().cell(id as u8).get()
+                else {
+                    continue;
+                };
+                if head.distance_to(opponent_head) > self.nearby_distance {
+                    continue;
+                }
+                if new_head.distance_to(opponent_head) >= head.distance_to(opponent_head) {
+                    continue;
+                }
+                if lead + growth > 0 {
+                    evaluation.score(*direction, 20, "Safe Head To Head");
+                } else {
+                    evaluation.score(*direction, -40, "Unsafe Head To Head");
+                }
+            }
+
+            let area_per_length = result.flooded_area[0] as u16 / length.max(1) as u16;
+            let cramped = area_per_length < self.space_factor as u16;
+            if wants_to_grow && !cramped {
+                let deficit = (self.lead as i16 - smallest_lead) as i32;
+                if let Some(turn) = result.food[0].iter().map(|&(_, turn)| turn).min() {
+                    evaluation.score(
+                        *direction,
+                        deficit * 0.max(20 - turn as i32),
+                        "Grow For Lead",
+                    );
+                }
+            } else if eats && cramped {
+                evaluation.score(*direction, -30, "Conserve Space");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{logic::general::direction::DIRECTIONS, read_game_state};
+
+    #[test]
+    fn test_length_section() {
+        let gamestate = read_game_state("requests/starvation_1.json");
+        let state = GameState::<BasicField>::from(&gamestate);
+        assert_eq!(LengthStrategy::leads(&state), [None, Some(-1), None, None]);
+
+        let floods: Vec<_> = DIRECTIONS
+            .iter()
+            .map(|&direction| {
+                let mut flooded: GameState<FloodFillField> = state.clone().into();
+                let result = flooded.flood_fill(direction);
+                (direction, flooded, result)
+            })
+            .collect();
+        let mut evaluation = Evaluation::new();
+        LengthStrategy::default().evaluate(&state, &floods, &mut evaluation);
+        let report = evaluation.report();
+        assert_eq!(report.sections[0].name, "Length");
+        let up = &report.sections[0].details[Direction::Up as usize];
+        assert!(up.iter().any(|detail| detail.detail == "Grow For Lead"));
+    }
+}
@@ -1,45 +1,133 @@
-use std::{env, time::Duration};
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex, PoisonError},
+    time::Duration,
+};
 
 use log::{info, warn};
 
 use crate::{
     OriginalDirection, OriginalGameState,
     logic::{
+        config,
         general::{
             direction::{DIRECTIONS, Direction},
             evaluation::Evaluation,
             field::{BasicField, FloodFillField},
-            game_state::GameState,
+            food_model::FoodModel,
+            game_state::{FloodFillResult, GameState},
+            moves::{MoveMatrix, MoveVector, Moves},
             snake::Snake,
         },
-        legacy::shared::brain::Brain,
+        legacy::shared::brain::{Brain, Decision, SearchSummary},
+        opponents::{self, Stance},
         single_gamestate_nodes::{
-            node::NodeStatus,
-            situation::{Situation, SituationMatch, SituationSet},
-            tree::Tree,
+            length_strategy::LengthStrategy,
+            node::{Node, NodeState, NodeStatus, Storage},
+            situation::{Situation, SituationFile, SituationMatch, SituationSet},
+            tree::{Tree, priority},
         },
     },
 };
 
 pub struct GamestateNodesSnake;
 
+mod length_strategy;
 mod node;
-mod situation;
+pub mod situation;
 mod tree;
 
+/// Health left after eating below which a direction is penalized
+const HEALTH_MARGIN: i32 = 10;
+/// Plies the simulation looks ahead at most. A fast tracked line has a single reply per ply and
+/// is simulated before the rest of the tree, so without a limit it runs as deep as the line goes
+/// and uses up the time of the move. No branching line gets near 64 plies in time.
+const SEARCH_DEPTH: u16 = 64;
+/// Plies within which the simulation looks for kills the opponents cannot escape
+const KILL_DEPTH: u16 = 3;
+/// Score of a forced kill on the next move, divided by the plies until the kill
+const FORCED_KILL_SCORE: i32 = 200;
+/// Least probability of spawning food in reach that avoids the elimination of a direction without food
+const MIN_SPAWN_CHANCE_TO_SURVIVE: f64 = 0.5;
+
+static SPECIAL_SITUATIONS: LazyLock<SituationSet> = LazyLock::new(|| {
+    SituationSet::parse(include_str!("situation/special.situations")).unwrap()
+});
+static FAST_TRACK_SITUATIONS: LazyLock<SituationSet> = LazyLock::new(|| {
+    SituationSet::parse(include_str!("situation/fast_track.situations")).unwrap()
+});
+static OPPONENT_SITUATIONS: LazyLock<SituationSet> = LazyLock::new(|| {
+    SituationSet::parse(include_str!("situation/opponents.situations")).unwrap()
+});
+/// Situation files by path, shared by all snakes naming the same file
+static SITUATION_FILES: LazyLock<Mutex<HashMap<String, SituationFile>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Priority function of the tree, see `tree::priority`
+#[derive(Clone, Copy)]
+enum SearchPriority {
+    BreadthFirst,
+    UncertainRootDirection,
+    LengthDifference,
+}
+
+impl SearchPriority {
+    fn function<S: NodeState>(self) -> fn(&Tree<S>, &Node<S>, &GameState<BasicField>) -> i32 {
+        match self {
+            SearchPriority::BreadthFirst => priority::breadth_first,
+            SearchPriority::UncertainRootDirection => priority::uncertain_root_direction,
+            SearchPriority::LengthDifference => priority::length_difference,
+        }
+    }
+}
+
+/// How the tree uses the opponent situations below the root, see `opponent_situation_set`
+#[derive(Clone, Copy, PartialEq)]
+enum OpponentSituations {
+    Ignore,
+    Restrict,
+    Prioritise,
+}
+
 struct EnvironmentConfig {
     simulation_time: Duration,
+    /// Nodes the search stops at, for results that do not depend on the speed of the machine
+    simulation_nodes: Option<usize>,
+    priority: SearchPriority,
+    storage: Storage,
+    opponent_situations: OpponentSituations,
 }
 
 impl EnvironmentConfig {
     fn read() -> Self {
         let simulation_time = Duration::from_millis(
-            env::var("SIMULATION_TIME_MS")
-                .ok()
+            config::param("SIMULATION_TIME_MS")
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(200),
         );
-        Self { simulation_time }
+        let simulation_nodes = config::param("SIMULATION_NODES").and_then(|v| v.parse().ok());
+        let priority = match config::param("SEARCH_PRIORITY").as_deref() {
+            Some("uncertain_root_direction") => SearchPriority::UncertainRootDirection,
+            Some("length_difference") => SearchPriority::LengthDifference,
+            _ => SearchPriority::BreadthFirst,
+        };
+        let storage = match config::param("SEARCH_STORAGE").as_deref() {
+            Some("packed") => Storage::Packed,
+            Some("recompute") => Storage::Recompute,
+            _ => Storage::Full,
+        };
+        let opponent_situations = match config::param("OPPONENT_SITUATIONS").as_deref() {
+            Some("restrict") => OpponentSituations::Restrict,
+            Some("prioritise") => OpponentSituations::Prioritise,
+            _ => OpponentSituations::Ignore,
+        };
+        Self {
+            simulation_time,
+            simulation_nodes,
+            priority,
+            storage,
+            opponent_situations,
+        }
     }
 }
 
@@ -49,100 +137,106 @@ impl GamestateNodesSnake {
     }
 
     pub fn fast_track_trigger_situation() -> Situation {
-        Situation::multi_recommending(
-            "
-                W . .
-                W A .
-                W N B
-                ",
-            [Some(Direction::Up), Some(Direction::Up), None, None],
-            0,
-            "Fast Track",
-        )
-        .full_symmetry()
-        .condition(|snakes| {
-            if let [
-                Snake::Alive { length: a, .. },
-                Snake::Alive { length: b, .. },
-                _,
-                _,
-            ] = snakes
+        FAST_TRACK_SITUATIONS.situations()[0].clone()
+    }
+
+    /// Situations of the file named by `SITUATIONS_FILE`, or the built-in ones without it.
+    /// With `SITUATIONS_RELOAD` set the file is read again whenever it was modified.
+    pub fn special_situation_set() -> SituationSet {
+        let Some(path) = config::param("SITUATIONS_FILE") else {
+            return SPECIAL_SITUATIONS.clone();
+        };
+        let mut files = SITUATION_FILES
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(file) = files.get_mut(&path) {
+            if config::param("SITUATIONS_RELOAD").is_some()
+                && let Err(e) = file.reload_if_changed()
             {
-                a <= b
-            } else {
-                false
+                warn!("Keeping the previous situations: {}", e);
+            }
+            return file.set().clone();
+        }
+        match SituationFile::load(&path) {
+            Ok(file) => {
+                let set = file.set().clone();
+                files.insert(path, file);
+                set
+            }
+            Err(e) => {
+                warn!("Using the built-in situations: {}", e);
+                SPECIAL_SITUATIONS.clone()
             }
-        })
+        }
     }
 
-    pub fn special_situation_set() -> SituationSet {
-        // Evaluate situations and return or avoid direction
-        let situation_set = SituationSet::new(vec![
-            // Kill by lead
-            Situation::recommending(
-                "
-                X A
-                ",
-                Direction::Left,
-                60,
-                "Grab Food",
-            )
-            .full_symmetry(),
-            Situation::recommending(
-                "
-                W N *
-                W B N
-                W . A
-                ",
-                Direction::Down,
-                100,
-                "Kill by Lead",
-            )
-            .full_symmetry(),
-            // Kill by follow
-            Situation::recommending(
-                "
-                W B .
-                W N A
-                ",
-                Direction::Up,
-                100,
-                "Kill by Follow",
-            )
-            .full_symmetry()
-            .condition(|snakes| {
-                if let [
-                    Snake::Alive { length: a, .. },
-                    Snake::Alive { length: b, .. },
-                    _,
-                    _,
-                ] = snakes
-                {
-                    a > b
-                } else {
-                    false
-                }
-            }),
-        ]);
-        situation_set
+    /// Situations ruling out opponents' replies in the tree below the root, used as set by
+    /// `OPPONENT_SITUATIONS`
+    pub fn opponent_situation_set() -> SituationSet {
+        OPPONENT_SITUATIONS.clone()
+    }
+
+    /// Checks a situation file before serving, see `special_situation_set`
+    pub fn check_situation_file(path: &str) -> Result<(), String> {
+        SituationSet::load(path).map(|_| ())
     }
 
     fn simulation(
         gamestate: GameState<BasicField>,
         evaluation: &mut Evaluation,
         env_config: &EnvironmentConfig,
-    ) -> [NodeStatus; 4] {
-        let mut tree = Tree::new(gamestate.clone())
+        stances: [Stance; 4],
+    ) -> ([NodeStatus; 4], SearchSummary) {
+        match env_config.storage {
+            Storage::Full => Self::search(Tree::new(gamestate), evaluation, env_config, stances),
+            storage => Self::search(
+                Tree::compact(gamestate, storage),
+                evaluation,
+                env_config,
+                stances,
+            ),
+        }
+    }
+
+    fn search<S: NodeState>(
+        tree: Tree<S>,
+        evaluation: &mut Evaluation,
+        env_config: &EnvironmentConfig,
+        stances: [Stance; 4],
+    ) -> ([NodeStatus; 4], SearchSummary) {
+        let fast_track = GamestateNodesSnake::fast_track_trigger_situation();
+        let mut tree = tree
             .all_root_directions()
             .dead_ancestor_pruning()
             .similarity_pruning(|_| 6)
+            .kill_seeking(KILL_DEPTH)
+            .max_depth(SEARCH_DEPTH)
             .fast_track(move |node| {
                 matches!(
-                    GamestateNodesSnake::fast_track_trigger_situation().check(node.gamestate()),
+                    fast_track.check(node.gamestate()),
                     Some(SituationMatch::Recommend(_))
                 )
             })
             .max_time(env_config.simulation_time);
+        if let Some(nodes) = env_config.simulation_nodes {
+            tree = tree.max_nodes(nodes);
+        }
+        // Breadth first is the order of a tree without a priority function, it is not scored
+        if !matches!(env_config.priority, SearchPriority::BreadthFirst) {
+            tree = tree.priority(env_config.priority.function());
+        }
+        if stances.contains(&Stance::Optimistic) {
+            tree = tree.restrict_moves(GamestateNodesSnake::optimistic_restriction(stances));
+        }
+        tree = match env_config.opponent_situations {
+            OpponentSituations::Ignore => tree,
+            OpponentSituations::Restrict => {
+                tree.situation_restrictions(GamestateNodesSnake::opponent_situation_set())
+            }
+            OpponentSituations::Prioritise => {
+                tree.situation_priorities(GamestateNodesSnake::opponent_situation_set())
+            }
+        };
         tree.simulate();
         let result = tree.result();
 
@@ -158,11 +252,121 @@ impl GamestateNodesSnake {
             }
         }
 
-        result
+        evaluation.new_section("Kill");
+        for (direction, kill) in DIRECTIONS.into_iter().zip(tree.kill_result()) {
+            if let Some(plies) = kill {
+                evaluation.score(direction, FORCED_KILL_SCORE / plies as i32, "Forced Kill");
+            }
+        }
+
+        (result, tree.summary())
+    }
+
+    /// Restricts the optimistic opponents below the root. The root statuses eliminate
+    /// directions, they have to hold against every reply.
+    fn optimistic_restriction<S: NodeState>(
+        stances: [Stance; 4],
+    ) -> impl Fn(&Node<S>, &mut MoveMatrix) + 'static {
+        move |node, move_matrix| {
+            if node.id().depth() > 0 {
+                GamestateNodesSnake::restrict_optimistic(node.gamestate(), &stances, move_matrix)
+            }
+        }
+    }
+
+    /// Optimistic opponents do not step next to our head when they would lose or draw the
+    /// head-to-head, unless they have no other move
+    fn restrict_optimistic(
+        gamestate: &GameState<BasicField>,
+        stances: &[Stance; 4],
+        move_matrix: &mut MoveMatrix,
+    ) {
+        let Snake::Alive {
+            head: our_head,
+            length: our_length,
+            ..
+        } = gamestate.snakes().cell(0).get()
+        else {
+            return;
+        };
+        for (id, stance) in stances.iter().enumerate().skip(1) {
+            if *stance != Stance::Optimistic {
+                continue;
+            }
+            let Snake::Alive { head, length, .. } = gamestate.snakes().cell(id as u8).get() else {
+                continue;
+            };
+            let Some(mut moves) = *move_matrix.get(id) else {
+                continue;
+            };
+            if length > our_length {
+                continue;
+            }
+            for direction in DIRECTIONS {
+                if (head + direction).distance_to(our_head) == 1 {
+                    moves[direction as usize] = false;
+                }
+            }
+            if moves.iter().any(|&valid| valid) {
+                move_matrix.set(id, MoveVector::new(Some(moves)));
+            }
+        }
+    }
+
+    /// Exhaustively checks which root directions survive `observed_moves.len() + 1` turns while
+    /// the opponents replay `observed_moves` (one entry per depth). Opponent moves that were not
+    /// observed, including all of the last turn, stay unrestricted.
+    pub fn surviving_directions(
+        gamestate: GameState<BasicField>,
+        observed_moves: Vec<Moves>,
+    ) -> [bool; 4] {
+        let depth = (observed_moves.len() + 1).min(Tree::MAX_DEPTH as usize) as u16;
+        let mut tree = Tree::new(gamestate)
+            .all_root_directions()
+            .max_depth(depth)
+            .restrict_moves(move |node, move_matrix| {
+                let Some(moves) = observed_moves.get(node.id().depth() as usize) else {
+                    return;
+                };
+                for (id, observed) in moves.iter().enumerate().skip(1) {
+                    if let Some(direction) = *observed
+                        && move_matrix.get(id).is_some()
+                    {
+                        move_matrix.set(id, MoveVector::from(direction));
+                    }
+                }
+            });
+        tree.simulate();
+        tree.result()
+            .map(|status| matches!(status, NodeStatus::AliveFor(_)))
     }
 
     pub fn logic_with_evaluation_result(&self, gamestate: &OriginalGameState) -> (OriginalDirection, String) {
+        let (decision, eval_string) = self.decide_with_evaluation_result(gamestate);
+        (decision.direction, eval_string)
+    }
+
+    fn decide_with_evaluation_result(&self, gamestate: &OriginalGameState) -> (Decision, String) {
+        let (evaluation, search) = self.evaluate(gamestate);
+        let direction = evaluation.result();
+        let eval_string = evaluation.to_string();
+        if config::param("LOG_EVAL").is_some() {
+            warn!("{eval_string}");
+        }
+
+        let decision = Decision {
+            direction: direction.into(),
+            reason: Some(evaluation.reason()),
+            search: Some(search),
+            evaluation: Some(evaluation.report()),
+        };
+        (decision, eval_string)
+    }
+
+    pub fn evaluate(&self, gamestate: &OriginalGameState) -> (Evaluation, SearchSummary) {
         let env_config = EnvironmentConfig::read();
+        let stances = opponents::profiles().stances(gamestate);
+        let food_model = FoodModel::from_game(&gamestate.game);
         let gamestate: GameState<BasicField> = gamestate.into();
         let mut evaluation = Evaluation::new();
 
@@ -170,7 +374,12 @@ impl GamestateNodesSnake {
         println!("{}", gamestate);
 
         // Simulation
-        GamestateNodesSnake::simulation(gamestate.clone(), &mut evaluation, &env_config);
+        let (_, search) = GamestateNodesSnake::simulation(
+            gamestate.clone(),
+            &mut evaluation,
+            &env_config,
+            stances,
+        );
 
         // Situations
         let situation_set = GamestateNodesSnake::special_situation_set();
@@ -178,6 +387,8 @@ impl GamestateNodesSnake {
 
         // Area
         evaluation.new_section("Capture");
+        let spawn_probability = food_model.spawn_probability(&gamestate);
+        let mut floods = Vec::new();
         for direction in DIRECTIONS {
             let mut state: GameState<FloodFillField> = gamestate.clone().into();
             let result = state.flood_fill(direction);
@@ -190,6 +401,8 @@ impl GamestateNodesSnake {
                 .count() as i32;
             evaluation.score(direction, squeezed_snakes * 100, "Squeezed Snakes");
             evaluation.score(direction, result.flooded_area[0] as i32, "Flooded Area");
+            let expected_food = FoodModel::expected_food_score(spawn_probability, &state, 0);
+            evaluation.score(direction, expected_food.round() as i32, "Expected Food");
 
             for &(coord, turn) in &result.food[0] {
                 if turn == 1  {
@@ -211,20 +424,69 @@ impl GamestateNodesSnake {
                     evaluation.score(direction, 5.max(15 - turn as i32), "Food");
                 }
             }
+            floods.push((direction, state, result));
+        }
+
+        // Health
+        evaluation.new_section("Health");
+        if let Snake::Alive { health, .. } = gamestate.snakes().cell(0).get() {
+            for (direction, state, result) in &floods {
+                GamestateNodesSnake::health(
+                    &mut evaluation,
+                    *direction,
+                    health,
+                    state,
+                    result,
+                    spawn_probability,
+                );
+            }
         }
 
+        // Length
+        LengthStrategy::read().evaluate(&gamestate, &floods, &mut evaluation);
+
         // Food hunting and general strategies should probably go here
         // failure_31_going_right_leads_to_death -> better general board positioning
         // failure_43_going_down_guarantees_getting_killed -> Single Child priority queue
-        // failure_46_go_for_kill -> Kill propagation in simulation
 
-        let direction = evaluation.result();
-        let eval_string = evaluation.to_string();
-        if env::var("LOG_EVAL").is_ok() {
-            warn!("{eval_string}");
-        }
+        (evaluation, search)
+    }
+}
 
-        (direction.into(), eval_string)
+impl GamestateNodesSnake {
+    /// Eliminates a direction if neither food on the board nor food likely to spawn can be
+    /// reached before starving, food counts only if we reach it before the opponents.
+    /// Penalizes directions that reach food with little health to spare.
+    fn health(
+        evaluation: &mut Evaluation,
+        direction: Direction,
+        health: u8,
+        state: &GameState<FloodFillField>,
+        result: &FloodFillResult,
+        spawn_probability: f64,
+    ) {
+        // Health is lost on the move and restored by food before starved snakes are eliminated
+        let nearest_food = result.food[0]
+            .iter()
+            .map(|&(_, turn)| turn)
+            .filter(|&turn| turn <= health)
+            .min();
+        match nearest_food {
+            Some(turn) => {
+                let margin = (health - turn) as i32;
+                if margin < HEALTH_MARGIN {
+                    evaluation.score(direction, (margin - HEALTH_MARGIN) * 10, "Health Margin");
+                }
+            }
+            None => {
+                let chance = FoodModel::spawn_in_reach(spawn_probability, state, 0, health);
+                if chance < MIN_SPAWN_CHANCE_TO_SURVIVE {
+                    evaluation.eliminate(direction, health.min(16));
+                } else {
+                    evaluation.score(direction, -100, "No Food In Reach");
+                }
+            }
+        }
     }
 }
 
@@ -232,4 +494,35 @@ impl Brain for GamestateNodesSnake {
     fn logic(&self, gamestate: &OriginalGameState) -> OriginalDirection {
         self.logic_with_evaluation_result(gamestate).0
     }
+
+    fn decide(&self, gamestate: &OriginalGameState) -> Decision {
+        self.decide_with_evaluation_result(gamestate).0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_game_state;
+
+    #[test]
+    fn test_optimistic_restriction_below_root() {
+        // Up meets the head of an opponent as long as us, the optimistic stance must not hide it
+        let state = GameState::<BasicField>::from(&read_game_state("requests/failure_11.json"));
+        let stances = [
+            Stance::Pessimistic,
+            Stance::Optimistic,
+            Stance::Optimistic,
+            Stance::Optimistic,
+        ];
+        let mut full = Tree::new(state.clone()).all_root_directions().max_depth(1);
+        full.simulate();
+        let mut optimistic = Tree::new(state)
+            .all_root_directions()
+            .max_depth(1)
+            .restrict_moves(GamestateNodesSnake::optimistic_restriction(stances));
+        optimistic.simulate();
+        assert_eq!(full.result()[Direction::Up as usize], NodeStatus::DeadIn(0));
+        assert_eq!(optimistic.result(), full.result());
+    }
 }
@@ -4,9 +4,11 @@ use std::{collections::HashSet, fmt::Display};
 use crate::logic::{
     general::{
         direction::{DIRECTIONS, Direction},
-        field::BasicField,
+        field::{BasicField, BitField},
         game_state::GameState,
         moves::{MoveMatrix, MoveVector},
+        snake::Snake,
+        snakes::Snakes,
     },
     single_gamestate_nodes::node::node_id::{DirectionVector, NodeId},
 };
@@ -14,6 +16,12 @@ use crate::logic::{
 pub mod node_id;
 mod node_stats;
 
+/// Narrows down the opponents' moves of a node before its children are spawned
+pub type MoveRestrictionFn<S = GameState<BasicField>> = dyn Fn(&Node<S>, &mut MoveMatrix);
+
+/// Decides whether a spawned child is simulated before the other queued nodes
+pub type FastTrackFn<S = GameState<BasicField>> = dyn Fn(&Node<S>) -> bool;
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum QueueStatus {
     Normal,
@@ -97,20 +105,149 @@ impl Display for NodeStatus {
     }
 }
 
+/// How the tree keeps the gamestates of its nodes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Storage {
+    /// Every node keeps its full gamestate
+    #[default]
+    Full,
+    /// Nodes keep their board packed into `BitField`s and are unpacked to be simulated
+    Packed,
+    /// Simulated nodes keep a packed board, the others only their snakes and are recomputed
+    /// from their parent to be simulated. Only leaves drop their board, so recomputing a node
+    /// replays a single move.
+    Recompute,
+}
+
+/// Gamestate kept by a node. `GameState<BasicField>` keeps it in full inside the node,
+/// `CompactState` as asked by a `Storage`.
+pub trait NodeState: Clone + Display + 'static {
+    fn from_gamestate(gamestate: GameState<BasicField>) -> Self;
+
+    /// Full gamestate, `None` while it is stored compactly
+    fn full(&self) -> Option<&GameState<BasicField>>;
+
+    fn snakes(&self) -> &Snakes;
+
+    /// Full gamestate if the state keeps a board, `None` if it has to be recomputed
+    fn unpacked(&self) -> Option<GameState<BasicField>>;
+
+    /// Stores the gamestate as `storage` asks for, `keep_board` keeps a packed board even when
+    /// it could be recomputed
+    fn pack(&mut self, storage: Storage, keep_board: bool);
+
+    /// Bytes the state holds outside of itself
+    fn heap_bytes(&self) -> usize;
+}
+
+impl NodeState for GameState<BasicField> {
+    fn from_gamestate(gamestate: GameState<BasicField>) -> Self {
+        gamestate
+    }
+
+    fn full(&self) -> Option<&GameState<BasicField>> {
+        Some(self)
+    }
+
+    fn snakes(&self) -> &Snakes {
+        GameState::snakes(self)
+    }
+
+    fn unpacked(&self) -> Option<GameState<BasicField>> {
+        Some(self.clone())
+    }
+
+    fn pack(&mut self, _storage: Storage, _keep_board: bool) {}
+
+    fn heap_bytes(&self) -> usize {
+        0
+    }
+}
+
+/// Gamestate of a node in a tree with `Storage::Packed` or `Storage::Recompute`, boxed so
+/// that nodes without a full gamestate stay small
+#[derive(Clone)]
+pub struct CompactState(State);
+
 #[derive(Clone)]
-pub struct Node {
+enum State {
+    Full(Box<GameState<BasicField>>),
+    Packed(Box<GameState<BitField>>),
+    Dropped(Snakes),
+}
+
+impl NodeState for CompactState {
+    fn from_gamestate(gamestate: GameState<BasicField>) -> Self {
+        CompactState(State::Full(Box::new(gamestate)))
+    }
+
+    fn full(&self) -> Option<&GameState<BasicField>> {
+        match &self.0 {
+            State::Full(gamestate) => Some(gamestate),
+            _ => None,
+        }
+    }
+
+    fn snakes(&self) -> &Snakes {
+        match &self.0 {
+            State::Full(gamestate) => gamestate.snakes(),
+            State::Packed(gamestate) => gamestate.snakes(),
+            State::Dropped(snakes) => snakes,
+        }
+    }
+
+    fn unpacked(&self) -> Option<GameState<BasicField>> {
+        match &self.0 {
+            State::Full(gamestate) => Some(gamestate.as_ref().clone()),
+            State::Packed(gamestate) => Some(gamestate.as_ref().into()),
+            State::Dropped(_) => None,
+        }
+    }
+
+    fn pack(&mut self, storage: Storage, keep_board: bool) {
+        let State::Full(gamestate) = &self.0 else {
+            return;
+        };
+        self.0 = match storage {
+            Storage::Full => return,
+            Storage::Recompute if !keep_board => State::Dropped(gamestate.snakes().clone()),
+            _ => State::Packed(Box::new(gamestate.as_ref().into())),
+        };
+    }
+
+    fn heap_bytes(&self) -> usize {
+        match &self.0 {
+            State::Full(_) => std::mem::size_of::<GameState<BasicField>>(),
+            State::Packed(_) => std::mem::size_of::<GameState<BitField>>(),
+            State::Dropped(_) => 0,
+        }
+    }
+}
+
+impl Display for CompactState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            State::Full(gamestate) => write!(f, "{}", gamestate),
+            State::Packed(gamestate) => write!(f, "{}", gamestate),
+            State::Dropped(_) => write!(f, "Board recomputed from the parent"),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Node<S = GameState<BasicField>> {
     id: NodeId,
-    gamestate: GameState<BasicField>,
+    state: S,
     children: [Option<Vec<(DirectionVector, NodeStatus)>>; 4],
     pinned_status: Option<NodeStatus>,
     queue_status: QueueStatus,
 }
 
-impl Node {
+impl<S: NodeState> Node<S> {
     pub fn new(id: NodeId, gamestate: GameState<BasicField>) -> Self {
         Self {
             id,
-            gamestate,
+            state: S::from_gamestate(gamestate),
             children: [None, None, None, None],
             pinned_status: None,
             queue_status: QueueStatus::Normal,
@@ -139,14 +276,18 @@ impl Node {
     }
 
     pub fn id(&self) -> NodeId {
-        self.id
+        self.id.clone()
+    }
+
+    pub fn id_ref(&self) -> &NodeId {
+        &self.id
     }
 
     pub fn status(&self) -> NodeStatus {
         if let Some(pinned) = self.pinned_status {
             return pinned;
         }
-        if !self.gamestate.is_alive(0) {
+        if !self.is_alive(0) {
             return NodeStatus::DeadIn(0);
         }
 
@@ -198,8 +339,39 @@ impl Node {
             })
     }
 
+    /// Full gamestate of the node. With compact storage only nodes that are being simulated or
+    /// were just spawned have one, `Tree::gamestate` works for every node.
     pub fn gamestate(&self) -> &GameState<BasicField> {
-        &self.gamestate
+        self.state
+            .full()
+            .unwrap_or_else(|| panic!("Gamestate of node {} is stored compactly", self.id))
+    }
+
+    pub fn snakes(&self) -> &Snakes {
+        self.state.snakes()
+    }
+
+    pub fn is_alive(&self, id: u8) -> bool {
+        matches!(self.snakes().cell(id).get(), Snake::Alive { .. })
+    }
+
+    /// Full gamestate if the node keeps a board, `None` if it has to be recomputed
+    pub fn unpacked(&self) -> Option<GameState<BasicField>> {
+        self.state.unpacked()
+    }
+
+    pub fn is_unpacked(&self) -> bool {
+        self.state.full().is_some()
+    }
+
+    pub fn unpack(&mut self, gamestate: GameState<BasicField>) {
+        self.state = S::from_gamestate(gamestate);
+    }
+
+    /// Stores the gamestate as `storage` asks for, `keep_board` keeps a packed board even when
+    /// it could be recomputed
+    pub fn pack(&mut self, storage: Storage, keep_board: bool) {
+        self.state.pack(storage, keep_board);
     }
 
     pub fn children(&self) -> [Option<Vec<(NodeId, NodeStatus)>>; 4] {
@@ -215,16 +387,17 @@ impl Node {
     pub fn simulate(
         &mut self,
         similarity_distance: Option<u8>,
-        fast_track_fn: Option<&dyn Fn(&Node) -> bool>,
-    ) -> Option<Vec<Node>> {
+        fast_track_fn: Option<&FastTrackFn<S>>,
+        move_restriction_fn: Option<&MoveRestrictionFn<S>>,
+    ) -> Option<Vec<Node<S>>> {
         // Check fast track once
 
-        'moveset: while let Some(move_matrix) = self.next_moveset() {
+        'moveset: while let Some(move_matrix) = self.next_moveset(move_restriction_fn) {
             let mut children = Vec::new();
             let direction: Direction = move_matrix.get(0).try_into().unwrap();
             let mut similarity_set: HashSet<u64> = HashSet::new();
             for moves in move_matrix {
-                let mut child_gamestate = self.gamestate.clone();
+                let mut child_gamestate = self.gamestate().clone();
                 child_gamestate.next_state(moves);
                 let child_id = self.id.child(moves);
 
@@ -281,7 +454,7 @@ impl Node {
 
     pub fn propagate_update_from_child(
         &mut self,
-        child_id: NodeId,
+        child_id: &NodeId,
         child_status: NodeStatus,
     ) -> bool {
         let old_status = self.status();
@@ -296,9 +469,16 @@ impl Node {
         self.status() != old_status
     }
 
-    fn next_moveset(&mut self) -> Option<MoveMatrix> {
-        let mut move_matrix = self.gamestate.valid_moves();
+    fn next_moveset(
+        &mut self,
+        move_restriction_fn: Option<&MoveRestrictionFn<S>>,
+    ) -> Option<MoveMatrix> {
+        let mut move_matrix = self.gamestate().valid_moves();
         let directions = move_matrix.get(0).unwrap();
+        // Restrictions only apply to the opponents, our own row is set per direction below
+        if let Some(move_restriction_fn) = move_restriction_fn {
+            move_restriction_fn(self, &mut move_matrix);
+        }
         for i in 0..4 {
             if self.children[i].is_none() {
                 if directions[i] {
@@ -316,7 +496,7 @@ impl Node {
     }
 }
 
-impl Display for Node {
+impl<S: NodeState> Display for Node<S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "\n{} {}", self.id, self.status())?;
         for (i, slot) in self.children.iter().enumerate() {
@@ -332,8 +512,7 @@ impl Display for Node {
                 }
             }
         }
-        writeln!(f, "\n{}", self.gamestate)?;
-        Ok(())
+        writeln!(f, "\n{}", self.state)
     }
 }
 
@@ -388,8 +567,8 @@ mod tests {
     fn simulate_exhausts_all_directions() {
         let mut node = make_root_node("requests/example_move_request.json");
         println!("{}", node);
-        while node.simulate(None, None).is_some() {
-            node.simulate(None, None);
+        while node.simulate(None, None, None).is_some() {
+            node.simulate(None, None, None);
         }
         // After exhaustion, all children slots should be filled
         assert!(
@@ -408,14 +587,14 @@ mod tests {
         }
         // Should return empty now
         println!("{}", node);
-        assert!(node.simulate(None, None).is_none());
+        assert!(node.simulate(None, None, None).is_none());
     }
 
     #[test]
     fn display_half_simulated_node() {
         let mut node = make_root_node("requests/test_game_start.json");
         // Simulate only the first two directions
-        node.simulate(None, None);
+        node.simulate(None, None, None);
         println!("{}", node);
     }
 }
@@ -454,7 +633,7 @@ mod benchmarks {
             // Fresh clone per iteration so each call starts from a clean, unsimulated node.
             let mut node = source_nodes[i % source_nodes.len()].clone();
             i += 1;
-            black_box(node.simulate(black_box(None), black_box(None)))
+            black_box(node.simulate(black_box(None), black_box(None), black_box(None)))
         });
     }
 
@@ -463,7 +642,7 @@ mod benchmarks {
         let nodes: Vec<Node> = test_nodes()
             .into_iter()
             .map(|mut n| {
-                n.simulate(None, None); // explore one direction
+                n.simulate(None, None, None); // explore one direction
                 n
             })
             .collect();
@@ -482,7 +661,7 @@ mod benchmarks {
             .into_iter()
             .filter_map(|mut parent| {
                 // Simulate one direction to populate a children list.
-                let children = parent.simulate(None, None)?;
+                let children = parent.simulate(None, None, None)?;
                 let (child_id, child_status) = children.first().map(|c| (c.id(), c.status()))?;
                 Some((parent, child_id, child_status))
             })
@@ -494,7 +673,7 @@ mod benchmarks {
             i += 1;
             let mut node = parent.clone();
             black_box(
-                node.propagate_update_from_child(black_box(*child_id), black_box(*child_status)),
+                node.propagate_update_from_child(black_box(child_id), black_box(*child_status)),
             )
         });
     }
@@ -1,4 +1,6 @@
 use std::fmt::{Debug, Display};
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
 use std::str::FromStr;
 
 use crate::logic::general::{direction::Direction, moves::Moves, snakes::SNAKES};
@@ -18,10 +20,13 @@ const NONE_SENTINEL: u8 = 0x1F; // 31
 const HEADER_BITS: u32 = DEPTH_BITS + NONE_START_BITS * SNAKES as u32; // 25
 /// Total bits in the backing store ([u128; 2]).
 const TOTAL_BITS: u32 = 256;
-/// Maximum supported tree depth: (256 − 25) / 8 = 28.
-const MAX_DEPTH: u8 = ((TOTAL_BITS - HEADER_BITS) / BITS_PER_LEVEL) as u8;
+/// Levels stored inline: (256 − 25) / 8 = 28.
+const INLINE_DEPTH: u16 = ((TOTAL_BITS - HEADER_BITS) / BITS_PER_LEVEL) as u16;
+/// Maximum supported tree depth, levels beyond `INLINE_DEPTH` are stored in an `Extension`.
+/// Each of those levels costs an allocation, so memory runs out long before the `u16` depth.
+const MAX_DEPTH: u16 = u16::MAX;
 /// Spare bits after all level data, used as user flags.
-const FLAGS_BITS: u32 = TOTAL_BITS - HEADER_BITS - MAX_DEPTH as u32 * BITS_PER_LEVEL; // 7
+const FLAGS_BITS: u32 = TOTAL_BITS - HEADER_BITS - INLINE_DEPTH as u32 * BITS_PER_LEVEL; // 7
 /// Bit position of the flags field (top of data[1]).
 const FLAGS_START: u32 = TOTAL_BITS - FLAGS_BITS;
 /// Initial data[0] value: depth=0, all none_start = NONE_SENTINEL (bits 5-24 all 1s).
@@ -44,9 +49,61 @@ pub type DirectionVector = [Option<Direction>; SNAKES as usize];
 /// `None` moves are not stored per-level. Instead, `none_start[snake]` records the first
 /// depth at which that snake went `None` (sentinel `31` = never). All levels ≥ `none_start`
 /// for that snake implicitly return `None`.
-#[derive(Hash, Eq, PartialEq, Clone, Copy)]
+///
+/// Levels beyond the 28 inline levels are kept in a list of `Extension`s shared with the
+/// ancestors, so ids up to the inline depth never allocate. The list makes `NodeId` 48 bytes
+/// and `Clone` instead of `Copy`, and ids deeper than the inline levels cost a reference count
+/// per clone and a walk along the list per `Hash`/`Eq`. Ids are capped at `MAX_DEPTH` (65535).
+#[derive(Hash, Eq, PartialEq, Clone)]
 pub struct NodeId {
     data: [u128; 2],
+    extension: Option<Rc<Extension>>,
+}
+
+/// A level beyond the inline levels, linked to the level before it. `Hash`, `Eq` and `Drop`
+/// walk the list in a loop, the derived ones would recurse once per level.
+struct Extension {
+    moves: DirectionVector,
+    /// Depth of the node id ending with this level
+    depth: u16,
+    previous: Option<Rc<Extension>>,
+}
+
+impl Extension {
+    fn levels(&self) -> impl Iterator<Item = &Extension> {
+        std::iter::successors(Some(self), |extension| extension.previous.as_deref())
+    }
+}
+
+impl PartialEq for Extension {
+    fn eq(&self, other: &Self) -> bool {
+        let mut levels = self.levels().zip(other.levels());
+        self.depth == other.depth
+            && levels.all(|(a, b)| std::ptr::eq(a, b) || a.moves == b.moves)
+    }
+}
+
+impl Eq for Extension {}
+
+impl Hash for Extension {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.depth.hash(state);
+        for extension in self.levels() {
+            extension.moves.hash(state);
+        }
+    }
+}
+
+impl Drop for Extension {
+    fn drop(&mut self) {
+        let mut previous = self.previous.take();
+        while let Some(extension) = previous {
+            previous = match Rc::try_unwrap(extension) {
+                Ok(mut extension) => extension.previous.take(),
+                Err(_) => None,
+            };
+        }
+    }
 }
 
 #[inline(always)]
@@ -82,7 +139,7 @@ fn dir_char(dir: Option<Direction>) -> char {
 }
 
 impl NodeId {
-    pub const MAX_DEPTH: u8 = MAX_DEPTH;
+    pub const MAX_DEPTH: u16 = MAX_DEPTH;
     /// Number of flag bits available (7). Valid flag values are `0 ..= (1 << MAX_FLAGS) - 1`.
     pub const MAX_FLAGS: u32 = FLAGS_BITS;
 
@@ -90,6 +147,7 @@ impl NodeId {
         // depth=0, all none_start = NONE_SENTINEL (bits 5-24 all 1s), rest zero.
         NodeId {
             data: [NONE_SENTINEL_INITIAL, 0],
+            extension: None,
         }
     }
 
@@ -146,32 +204,52 @@ impl NodeId {
         );
     }
 
-    pub fn depth(&self) -> u8 {
-        self.read_bits(0, DEPTH_BITS) as u8
+    pub fn depth(&self) -> u16 {
+        match &self.extension {
+            Some(extension) => extension.depth,
+            None => self.inline_depth(),
+        }
+    }
+
+    #[inline(always)]
+    fn inline_depth(&self) -> u16 {
+        self.read_bits(0, DEPTH_BITS) as u16
     }
 
     pub fn child(&self, moves: Moves) -> Self {
-        let mut child = *self;
+        let mut child = self.clone();
         child.push(moves);
         child
     }
 
     pub fn push(&mut self, moves: Moves) {
         let depth = self.depth();
-        debug_assert!(
-            depth < MAX_DEPTH,
-            "Maximum tree depth ({MAX_DEPTH}) exceeded"
-        );
+        assert!(depth < MAX_DEPTH, "Maximum tree depth ({MAX_DEPTH}) exceeded");
+        if depth >= INLINE_DEPTH {
+            for (snake, &dir) in moves.iter().enumerate() {
+                debug_assert!(
+                    dir.is_none() || self.last_direction_for(snake as u8) != Some(None),
+                    "Snake {} already went None",
+                    snake
+                );
+            }
+            self.extension = Some(Rc::new(Extension {
+                moves,
+                depth: depth + 1,
+                previous: self.extension.take(),
+            }));
+            return;
+        }
         let mut encoded: u128 = 0;
         for (i, &dir) in moves.iter().enumerate() {
             match dir {
                 None => {
                     let ns = self.none_start(i as u8);
                     if ns == NONE_SENTINEL {
-                        self.set_none_start(i as u8, depth);
+                        self.set_none_start(i as u8, depth as u8);
                     } else {
                         debug_assert!(
-                            ns < depth,
+                            (ns as u16) < depth,
                             "Snake {} none_start {} should be < depth {}",
                             i,
                             ns,
@@ -198,15 +276,21 @@ impl NodeId {
     }
 
     pub fn parent(&self) -> Option<Self> {
-        let depth = self.depth();
+        if let Some(extension) = &self.extension {
+            return Some(NodeId {
+                data: self.data,
+                extension: extension.previous.clone(),
+            });
+        }
+        let depth = self.inline_depth();
         if depth == 0 {
             return None;
         }
         let last = depth - 1;
-        let mut parent = *self;
+        let mut parent = self.clone();
         // Reset none_start for any snake that first went None at the level being removed.
         for snake in 0..SNAKES as u8 {
-            if self.none_start(snake) == last {
+            if self.none_start(snake) as u16 == last {
                 parent.set_none_start(snake, NONE_SENTINEL);
             }
         }
@@ -252,11 +336,18 @@ impl NodeId {
 
     /// Returns the direction of `snake` at `level`.
     /// Outer `None` = level >= depth. Inner `None` = snake went None at or before this level.
-    pub fn direction_at(&self, level: u8, snake: u8) -> Option<Option<Direction>> {
+    pub fn direction_at(&self, level: u16, snake: u8) -> Option<Option<Direction>> {
         if level >= self.depth() {
             return None;
         }
-        if level >= self.none_start(snake) {
+        if level >= INLINE_DEPTH {
+            let mut extension = self.extension.as_ref()?;
+            while extension.depth > level + 1 {
+                extension = extension.previous.as_ref()?;
+            }
+            return Some(extension.moves[snake as usize]);
+        }
+        if level >= self.none_start(snake) as u16 {
             return Some(None);
         }
         let shift = HEADER_BITS + level as u32 * BITS_PER_LEVEL + snake as u32 * BITS_PER_SNAKE;
@@ -385,7 +476,7 @@ mod tests {
         let c1 = root.child([Some(Down), Some(Right), Some(Up), Some(Left)]);
         let c2 = c1.child([Some(Up), Some(Up), Some(Down), Some(Down)]);
 
-        assert_eq!(c2.parent(), Some(c1));
+        assert_eq!(c2.parent(), Some(c1.clone()));
         assert_eq!(c1.parent(), Some(root));
     }
 
@@ -447,6 +538,11 @@ mod tests {
         assert_eq!(node.last_direction_for(3), Some(None));
     }
 
+    #[test]
+    fn node_id_size() {
+        assert_eq!(std::mem::size_of::<NodeId>(), 48);
+    }
+
     #[test]
     fn max_depth_reachable() {
         let mut node = NodeId::new();
@@ -465,6 +561,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn deep_path_beyond_inline_depth() {
+        let moves = |level: u16| -> Moves {
+            let dir = [Up, Down, Left, Right][level as usize % 4];
+            // Snake 3 goes None after the inline levels
+            [Some(dir), Some(Up), Some(dir), (level < 30).then_some(Left)]
+        };
+        let mut node = NodeId::new();
+        let mut path = vec![node.clone()];
+        for level in 0..40 {
+            node = node.child(moves(level));
+            path.push(node.clone());
+        }
+        assert_eq!(node.depth(), 40);
+        assert_eq!(node.direction_at(5, 0), Some(Some(Down)));
+        assert_eq!(node.direction_at(33, 0), Some(Some(Down)));
+        assert_eq!(node.direction_at(29, 3), Some(Some(Left)));
+        assert_eq!(node.direction_at(30, 3), Some(None));
+        assert_eq!(node.direction_at(40, 0), None);
+        assert_eq!(node.last_directions(), Some(moves(39)));
+
+        // Independently built ids are equal and hash alike
+        let parsed: NodeId = node.to_string().parse().unwrap();
+        assert_eq!(parsed, node);
+        let set: std::collections::HashSet<NodeId> = path.iter().cloned().collect();
+        assert!(set.contains(&parsed));
+        assert_eq!(set.len(), 41);
+
+        // Walking up leaves the extension at the inline depth
+        for depth in (0..40).rev() {
+            node = node.parent().unwrap();
+            assert_eq!(node, path[depth]);
+        }
+        assert!(path[INLINE_DEPTH as usize].extension.is_none());
+        assert!(path[INLINE_DEPTH as usize + 1].extension.is_some());
+    }
+
     #[test]
     fn four_level_display() {
         let node = NodeId::new()
@@ -576,7 +709,7 @@ mod benchmarks {
             let _ = black_box(node.last_directions());
             let _ = black_box(node.direction_at(2, 1));
             // Walk back up
-            while let Some(parent) = black_box(node).parent() {
+            while let Some(parent) = black_box(&node).parent() {
                 node = parent;
             }
             black_box(node)
@@ -1,8 +1,11 @@
-use super::Node;
+use super::{Node, NodeState};
+use crate::logic::{
+    general::moves::MoveMatrix,
+    single_gamestate_nodes::node::{NodeStatus, node_id::DirectionVector},
+};
 
-impl Node {
-    pub fn count_potential_children_all(&self) -> [usize; 4] {
-        let move_matrix = self.gamestate.valid_moves();
+impl<S: NodeState> Node<S> {
+    pub fn count_potential_children_all(&self, move_matrix: &MoveMatrix) -> [usize; 4] {
         let valid_directions = move_matrix.get(0).unwrap();
         // Product of other snakes' valid move counts (snake 0 is fixed to 1 direction)
         let others_product: usize = (1..4).map(|i| move_matrix.get(i).count_valid(1)).product();
@@ -15,8 +18,7 @@ impl Node {
         result
     }
 
-    pub fn count_potential_children_from_evaluated_directions(&self) -> [usize; 4] {
-        let move_matrix = self.gamestate.valid_moves();
+    pub fn count_potential_children_from_evaluated_directions(&self, move_matrix: &MoveMatrix) -> [usize; 4] {
         let valid_directions = move_matrix.get(0).unwrap();
         // Product of other snakes' valid move counts (snake 0 is fixed to 1 direction)
         let others_product: usize = (1..4).map(|i| move_matrix.get(i).count_valid(1)).product();
@@ -28,4 +30,17 @@ impl Node {
         }
         result
     }
+
+    /// Bytes the node holds outside of itself
+    pub fn heap_bytes(&self) -> usize {
+        let state = self.state.heap_bytes();
+        let child_size = std::mem::size_of::<(DirectionVector, NodeStatus)>();
+        let children: usize = self
+            .children
+            .iter()
+            .flatten()
+            .map(|children| children.capacity() * child_size)
+            .sum();
+        state + children
+    }
 }
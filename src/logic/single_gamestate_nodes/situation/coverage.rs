@@ -0,0 +1,190 @@
+use super::{SituationMatch, SituationSet};
+use crate::logic::general::{direction::Direction, field::BasicField, game_state::GameState};
+use std::fmt;
+use tabled::{
+    builder::Builder,
+    settings::{Alignment, Style, object::Columns},
+};
+
+/// States listed per situation whose labels contradict it
+const MAX_DISAGREEING: usize = 5;
+
+/// How often a situation matched and whether its own direction agreed with the labelled moves
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SituationStats {
+    /// Name of the set the situation belongs to
+    pub set: String,
+    pub detail: String,
+    pub score: i32,
+    /// Matches per symmetry variant, in the order the variants are checked
+    pub variants: Vec<u32>,
+    pub agreed: u32,
+    pub disagreed: u32,
+    /// Matches without a label for the direction, or without a direction for our snake
+    pub unlabelled: u32,
+    /// First states whose labels contradict the situation
+    pub disagreeing: Vec<String>,
+}
+
+impl SituationStats {
+    pub fn matches(&self) -> u32 {
+        self.variants.iter().sum()
+    }
+
+    /// Share of the labelled matches that agreed, `None` without labelled matches
+    pub fn agreement(&self) -> Option<f64> {
+        let labelled = self.agreed + self.disagreed;
+        (labelled > 0).then(|| self.agreed as f64 / labelled as f64)
+    }
+}
+
+/// Runs labelled states through situation sets and counts matches and agreement per situation
+#[derive(Default)]
+pub struct Coverage<'a> {
+    sets: Vec<&'a SituationSet>,
+    pub states: u32,
+    pub situations: Vec<SituationStats>,
+}
+
+impl<'a> Coverage<'a> {
+    /// Adds the situations of a set, reported under `name`
+    pub fn set(mut self, name: &str, set: &'a SituationSet) -> Self {
+        self.situations
+            .extend(set.situations().iter().map(|situation| SituationStats {
+                set: name.to_string(),
+                detail: situation.detail().to_string(),
+                score: situation.score(),
+                variants: vec![0; situation.variants()],
+                ..Default::default()
+            }));
+        self.sets.push(set);
+        self
+    }
+
+    /// Checks every situation against a state. A recommendation agrees with a `good` direction
+    /// and disagrees with a `bad` one, an avoidance the other way round.
+    pub fn add(
+        &mut self,
+        name: &str,
+        gamestate: &GameState<BasicField>,
+        good: &[Direction],
+        bad: &[Direction],
+    ) {
+        self.states += 1;
+        let situations = self.sets.iter().flat_map(|set| set.situations());
+        for (situation, stats) in situations.zip(&mut self.situations) {
+            let Some((variant, result)) = situation.check_variant(gamestate) else {
+                continue;
+            };
+            stats.variants[variant] += 1;
+            let (direction, recommend) = match result {
+                SituationMatch::Recommend(directions) => (directions[0], true),
+                SituationMatch::Avoid(directions) => (directions[0], false),
+            };
+            let Some(direction) = direction else {
+                stats.unlabelled += 1;
+                continue;
+            };
+            let (agreeing, disagreeing) = if recommend { (good, bad) } else { (bad, good) };
+            if agreeing.contains(&direction) {
+                stats.agreed += 1;
+            } else if disagreeing.contains(&direction) {
+                stats.disagreed += 1;
+                if stats.disagreeing.len() < MAX_DISAGREEING {
+                    stats.disagreeing.push(name.to_string());
+                }
+            } else {
+                stats.unlabelled += 1;
+            }
+        }
+    }
+}
+
+impl fmt::Display for Coverage<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut builder = Builder::default();
+        builder.push_record([
+            "Set",
+            "Situation",
+            "Score",
+            "Matches",
+            "Variants",
+            "Agreed",
+            "Disagreed",
+            "Unlabelled",
+            "Agreement",
+        ]);
+        for stats in &self.situations {
+            let variants: Vec<String> = stats.variants.iter().map(ToString::to_string).collect();
+            builder.push_record([
+                stats.set.clone(),
+                stats.detail.clone(),
+                stats.score.to_string(),
+                stats.matches().to_string(),
+                variants.join(" "),
+                stats.agreed.to_string(),
+                stats.disagreed.to_string(),
+                stats.unlabelled.to_string(),
+                stats
+                    .agreement()
+                    .map_or("-".to_string(), |a| format!("{:.1}%", a * 100.0)),
+            ]);
+        }
+        let mut table = builder.build();
+        table
+            .with(Style::rounded())
+            .modify(Columns::new(2..), Alignment::right());
+        write!(f, "{}", table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_game_state;
+
+    #[test]
+    fn test_coverage() {
+        let special = SituationSet::parse(
+            "situation Go Up\n\
+             recommend U\n\
+             score 10\n\
+             pattern\n\
+             ~\n\
+             A\n\
+             end\n",
+        )
+        .unwrap();
+        let opponents = SituationSet::parse(
+            "situation Cornered\n\
+             avoid U\n\
+             symmetry full\n\
+             pattern\n\
+             W W W\n\
+             W A W\n\
+             end\n",
+        )
+        .unwrap();
+        let gamestate = read_game_state("requests/starvation_1.json");
+        let state = GameState::<BasicField>::from(&gamestate);
+        let mut coverage = Coverage::default()
+            .set("special", &special)
+            .set("opponents", &opponents);
+        coverage.add("good", &state, &[Direction::Up], &[]);
+        coverage.add("bad", &state, &[], &[Direction::Up]);
+        coverage.add("none", &state, &[], &[]);
+
+        let up = &coverage.situations[0];
+        assert_eq!(up.variants, vec![3]);
+        assert_eq!((up.agreed, up.disagreed, up.unlabelled), (1, 1, 1));
+        assert_eq!(up.disagreeing, vec!["bad".to_string()]);
+        assert_eq!(up.agreement(), Some(0.5));
+
+        let cornered = &coverage.situations[1];
+        assert_eq!(cornered.set, "opponents");
+        assert_eq!(cornered.variants.len(), 4);
+        assert_eq!(cornered.matches(), 0);
+        assert_eq!(cornered.agreement(), None);
+        assert!(coverage.to_string().contains("Cornered"));
+    }
+}
@@ -0,0 +1,293 @@
+use super::{SituationMatch, SituationPattern};
+use crate::logic::general::{
+    coord::Coord,
+    direction::{DIRECTIONS, Direction},
+    field::BasicField,
+    game_state::GameState,
+    snake::Snake,
+    snakes::SNAKES,
+};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write},
+};
+
+/// Vocabulary used to encode the neighbourhood of our head
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    /// Most specific field per cell: bodies, tails, ranked heads, food and hazards
+    Fine,
+    /// Only walls, snakes, free cells and ranked heads, to find patterns with more support
+    Coarse,
+}
+
+/// States whose neighbourhoods are equal modulo rotation and reflection, with the labelled
+/// directions counted in the frame of the canonical grid
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Cluster {
+    pub good: [u32; 4],
+    pub bad: [u32; 4],
+    pub states: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    /// Pattern rows with up on top
+    pub rows: Vec<String>,
+    pub direction: Direction,
+    pub recommend: bool,
+    /// Labelled states in the cluster that judged `direction`
+    pub support: u32,
+    /// Share of the support agreeing with the recommendation or avoidance
+    pub accuracy: f64,
+    pub states: Vec<String>,
+}
+
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = if self.recommend { "recommend" } else { "avoid" };
+        write!(
+            f,
+            "{} {} in {} states ({:.0}%)",
+            verb,
+            self.direction,
+            self.support,
+            self.accuracy * 100.0
+        )
+    }
+}
+
+/// Collects neighbourhoods around our head from labelled states and proposes situations
+pub struct Miner {
+    radius: u8,
+    encoding: Encoding,
+    clusters: BTreeMap<Vec<String>, Cluster>,
+}
+
+impl Miner {
+    pub fn new(radius: u8, encoding: Encoding) -> Self {
+        Self {
+            radius,
+            encoding,
+            clusters: BTreeMap::new(),
+        }
+    }
+
+    pub fn clusters(&self) -> &BTreeMap<Vec<String>, Cluster> {
+        &self.clusters
+    }
+
+    fn encode(&self, gamestate: &GameState<BasicField>, coord: Coord) -> char {
+        let Some(field) = gamestate.board().cell_coord(coord).map(|c| c.get()) else {
+            return 'W';
+        };
+        let lengths = gamestate.snakes().lengths();
+        let tail_stack = (0..SNAKES as u8).find_map(|id| match gamestate.snakes().cell(id).get() {
+            Snake::Alive { tail, stack, .. } if tail == coord => Some(stack),
+            _ => None,
+        });
+        match (field, self.encoding) {
+            (BasicField::Snake { id: 0, next: None }, _) => 'A',
+            (BasicField::Snake { id, next: None }, _) => {
+                match lengths[id as usize].cmp(&lengths[0]) {
+                    std::cmp::Ordering::Greater => '+',
+                    std::cmp::Ordering::Less => '-',
+                    std::cmp::Ordering::Equal => '=',
+                }
+            }
+            (BasicField::Snake { .. }, Encoding::Coarse) => 'N',
+            (BasicField::Snake { .. }, Encoding::Fine) if tail_stack == Some(0) => 'T',
+            (BasicField::Snake { .. }, Encoding::Fine) if tail_stack.is_some() => 'Z',
+            (BasicField::Snake { id: 0, .. }, Encoding::Fine) => 'O',
+            (BasicField::Snake { .. }, Encoding::Fine) => 'S',
+            (_, Encoding::Coarse) => '.',
+            (BasicField::Food, Encoding::Fine) => 'X',
+            (BasicField::Empty, Encoding::Fine) => {
+                if gamestate.board().hazards().contains(coord) {
+                    'H'
+                } else {
+                    '.'
+                }
+            }
+        }
+    }
+
+    /// Neighbourhood of our head in the same grid encoding as situation patterns. The result
+    /// maps every direction to itself, so transforms of the pattern tell where directions go.
+    fn neighbourhood(&self, gamestate: &GameState<BasicField>) -> Option<SituationPattern> {
+        let Snake::Alive { head, .. } = gamestate.snakes().cell(0).get() else {
+            return None;
+        };
+        let radius = self.radius as i8;
+        let mut text = String::new();
+        for dy in (-radius..=radius).rev() {
+            for dx in -radius..=radius {
+                let c = self.encode(gamestate, Coord::new(head.x + dx, head.y + dy));
+                let _ = write!(text, "{} ", c);
+            }
+            text.push('\n');
+        }
+        let identity = SituationMatch::Recommend(DIRECTIONS.map(Some));
+        SituationPattern::try_parse(&text, identity).ok()
+    }
+
+    /// The smallest of the eight symmetric variants of a pattern
+    fn canonical(pattern: SituationPattern) -> SituationPattern {
+        let mut variants = vec![pattern];
+        for i in 0..3 {
+            let rotated = variants[i].rotate_cw();
+            variants.push(rotated);
+        }
+        for i in 0..4 {
+            let mirrored = variants[i].mirror_x();
+            variants.push(mirrored);
+        }
+        variants
+            .into_iter()
+            .min_by_key(|variant| variant.rows())
+            .unwrap()
+    }
+
+    /// Adds a labelled state, directions that are no valid move are not counted.
+    /// Grids that are symmetric in themselves count the labels in the frame of the first
+    /// transform reaching the canonical grid.
+    pub fn add(
+        &mut self,
+        name: &str,
+        gamestate: &GameState<BasicField>,
+        good: &[Direction],
+        bad: &[Direction],
+    ) {
+        let Some(pattern) = self.neighbourhood(gamestate) else {
+            return;
+        };
+        let valid = gamestate.valid_moves().get(0);
+        let canonical = Self::canonical(pattern);
+        let SituationMatch::Recommend(mapping) = canonical.result else {
+            unreachable!("neighbourhoods recommend every direction")
+        };
+        let cluster = self.clusters.entry(canonical.rows()).or_default();
+        cluster.states.push(name.to_string());
+        for (direction, mapped) in DIRECTIONS.iter().zip(mapping) {
+            let mapped = mapped.unwrap() as usize;
+            if !valid.is_valid(*direction) {
+                continue;
+            }
+            if good.contains(direction) {
+                cluster.good[mapped] += 1;
+            }
+            if bad.contains(direction) {
+                cluster.bad[mapped] += 1;
+            }
+        }
+    }
+
+    /// Directions of clusters judged by at least `min_support` states with at least
+    /// `min_accuracy` agreement, the best supported first
+    pub fn candidates(&self, min_support: u32, min_accuracy: f64) -> Vec<Candidate> {
+        let mut candidates = Vec::new();
+        for (rows, cluster) in &self.clusters {
+            for direction in DIRECTIONS {
+                let (good, bad) = (
+                    cluster.good[direction as usize],
+                    cluster.bad[direction as usize],
+                );
+                let support = good + bad;
+                if support == 0 || support < min_support {
+                    continue;
+                }
+                let good_share = good as f64 / support as f64;
+                let (recommend, accuracy) = if good_share >= 1.0 - good_share {
+                    (true, good_share)
+                } else {
+                    (false, 1.0 - good_share)
+                };
+                if accuracy < min_accuracy {
+                    continue;
+                }
+                candidates.push(Candidate {
+                    rows: rows.clone(),
+                    direction,
+                    recommend,
+                    support,
+                    accuracy,
+                    states: cluster.states.clone(),
+                });
+            }
+        }
+        candidates.sort_by(|a, b| {
+            b.support
+                .cmp(&a.support)
+                .then(b.accuracy.total_cmp(&a.accuracy))
+        });
+        candidates
+    }
+}
+
+/// Candidates in the situation file format, with their statistics as comments
+pub fn export(candidates: &[Candidate]) -> String {
+    let mut out = String::new();
+    for (index, candidate) in candidates.iter().enumerate() {
+        let _ = writeln!(out, "# {}", candidate);
+        let examples: Vec<&str> = candidate.states.iter().take(3).map(String::as_str).collect();
+        let _ = writeln!(out, "# e.g. {}", examples.join(", "));
+        let _ = writeln!(out, "situation Mined {}", index + 1);
+        if candidate.recommend {
+            let _ = writeln!(out, "recommend {}", candidate.direction);
+            let _ = writeln!(out, "score {}", (candidate.accuracy * 100.0).round());
+        } else {
+            let _ = writeln!(out, "avoid {}", candidate.direction);
+        }
+        let _ = writeln!(out, "symmetry full");
+        let _ = writeln!(out, "pattern");
+        for row in &candidate.rows {
+            let _ = writeln!(out, "    {}", row);
+        }
+        let _ = writeln!(out, "end");
+        let _ = writeln!(out);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{logic::single_gamestate_nodes::situation::SituationSet, read_game_state};
+
+    #[test]
+    fn test_mining_roundtrip() {
+        let gamestate = read_game_state("requests/starvation_1.json");
+        let state = GameState::<BasicField>::from(&gamestate);
+        for encoding in [Encoding::Fine, Encoding::Coarse] {
+            let mut miner = Miner::new(2, encoding);
+            miner.add("a", &state, &[Direction::Up], &[Direction::Left, Direction::Down]);
+            miner.add("b", &state, &[Direction::Up], &[]);
+            assert_eq!(miner.clusters().len(), 1);
+
+            let candidates = miner.candidates(2, 0.8);
+            assert_eq!(candidates.len(), 1);
+            assert!(candidates[0].recommend);
+            assert_eq!(candidates[0].support, 2);
+
+            // The exported pattern is canonical, the full symmetry finds the original frame again
+            let set = SituationSet::parse(&export(&candidates)).unwrap();
+            assert_eq!(
+                set.situations()[0].check(&state),
+                Some(SituationMatch::Recommend([Some(Direction::Up), None, None, None]))
+            );
+        }
+    }
+
+    #[test]
+    fn test_canonical_is_symmetry_invariant() {
+        let gamestate = read_game_state("requests/test_move_request.json");
+        let state = GameState::<BasicField>::from(&gamestate);
+        let miner = Miner::new(1, Encoding::Fine);
+        let pattern = miner.neighbourhood(&state).unwrap();
+        let expected = Miner::canonical(pattern.clone());
+        let rotated = pattern.rotate_cw().mirror_x();
+        let canonical = Miner::canonical(rotated);
+        assert_eq!(canonical.rows(), expected.rows());
+        assert!(canonical.result == expected.result);
+    }
+}
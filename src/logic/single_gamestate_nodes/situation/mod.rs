@@ -1,11 +1,15 @@
+pub mod coverage;
+pub mod mining;
 mod situation_field;
+mod situation_file;
 
 use log::debug;
 use situation_field::SituationField;
-use std::fmt;
+pub use situation_file::{SituationFile, SnakeCondition};
+use std::{fmt, fs};
 
 use crate::logic::general::{
-    direction::{Direction}, evaluation::Evaluation, field::BasicField, game_state::GameState, snake::Snake, snakes::SNAKES
+    coord::Coord, direction::{Direction}, moves::{MoveMatrix, MoveVector}, evaluation::Evaluation, field::BasicField, game_state::GameState, snake::Snake, snakes::SNAKES
 };
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -14,6 +18,7 @@ pub enum SituationMatch {
     Avoid([Option<Direction>; SNAKES]),
 }
 
+#[derive(Clone)]
 struct SituationPattern {
     fields: Vec<SituationField>,
     width: usize,
@@ -23,7 +28,7 @@ struct SituationPattern {
 }
 
 impl SituationPattern {
-    fn parse(str: &str, result: SituationMatch) -> Self {
+    fn try_parse(str: &str, result: SituationMatch) -> Result<Self, String> {
         let lines: Vec<&str> = str
             .lines()
             .map(|l| l.trim())
@@ -34,23 +39,30 @@ impl SituationPattern {
         let mut fields = Vec::with_capacity(lines.len() * width);
 
         for line in lines.iter().rev() {
+            if line.split_whitespace().count() != width {
+                return Err(format!("Situation rows must have {} fields: {}", width, line));
+            }
             for token in line.split_whitespace() {
-                fields.push(SituationField::from(token.chars().next().unwrap()));
+                let c = token.chars().next().unwrap();
+                fields.push(
+                    SituationField::parse(c)
+                        .ok_or_else(|| format!("Invalid character for SituationField: {}", c))?,
+                );
             }
         }
 
         let head_pos = fields
             .iter()
             .position(|f| matches!(f, SituationField::OwnHead))
-            .expect("Situation must contain an OwnHead (A) field");
+            .ok_or("Situation must contain an OwnHead (A) field")?;
 
-        Self {
+        Ok(Self {
             head_dx: (head_pos % width) as isize,
             head_dy: (head_pos / width) as isize,
             fields,
             width,
             result,
-        }
+        })
     }
 
     // 90° clockwise rotation (y-up coordinate system).
@@ -122,7 +134,8 @@ impl SituationPattern {
             row.iter().enumerate().all(|(dx, field)| {
                 let x = base_x + dx as isize;
                 let y = base_y + dy as isize;
-                let cell = gamestate.board().cell(x as i8, y as i8).map(|c| c.get());
+                let coord = Coord::new(x as i8, y as i8);
+                let cell = gamestate.board().cell_coord(coord).map(|c| c.get());
                 if let SituationField::OtherHead(idx) = field {
                     if let Some(BasicField::Snake { id, next: None }) = cell {
                         if id != 0 {
@@ -134,7 +147,7 @@ impl SituationPattern {
                     }
                     return false;
                 }
-                field.check(cell)
+                field.check(gamestate, coord)
             })
         });
         if matches {
@@ -191,22 +204,34 @@ impl fmt::Display for SituationMatch {
     }
 }
 
+impl SituationPattern {
+    /// Rows of the pattern with up on top, as written in situation files
+    fn rows(&self) -> Vec<String> {
+        self.fields
+            .chunks(self.width)
+            .rev()
+            .map(|row| {
+                row.iter()
+                    .map(|f| f.display_char())
+                    .flat_map(|c| [c, ' '])
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+}
+
 impl fmt::Display for SituationPattern {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for row in self.fields.chunks(self.width).rev() {
-            let line: String = row
-                .iter()
-                .map(|f| f.display_char())
-                .flat_map(|c| [c, ' '])
-                .collect::<String>()
-                .trim_end()
-                .to_string();
-            writeln!(f, "{}", line)?;
+        for row in self.rows() {
+            writeln!(f, "{}", row)?;
         }
         write!(f, "=> {}", self.result)
     }
 }
 
+#[derive(Clone)]
 pub struct SituationSet {
     situations: Vec<Situation>,
 }
@@ -216,6 +241,56 @@ impl SituationSet {
         Self { situations }
     }
 
+    /// Reads situations in the format described at `situation_file::parse_situations`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        situation_file::parse_situations(text).map(Self::new)
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+        Self::parse(&content).map_err(|e| format!("Cannot parse {}: {}", path, e))
+    }
+
+    pub fn situations(&self) -> &[Situation] {
+        &self.situations
+    }
+
+    /// Narrows the opponents' moves by the directions matched situations give for them:
+    /// a recommended direction becomes the only move, an avoided direction is removed unless it
+    /// is the last move left. Our own moves and the directions of invalid moves are ignored.
+    /// Every matching symmetry variant applies, so one situation can restrict several opponents.
+    pub fn restrict_opponents(&self, gamestate: &GameState<BasicField>, move_matrix: &mut MoveMatrix) {
+        let results = self
+            .situations
+            .iter()
+            .flat_map(|situation| situation.matches(gamestate));
+        for (_, result) in results {
+            for id in 1..SNAKES {
+                let Some(mut moves) = *move_matrix.get(id) else {
+                    continue;
+                };
+                match result {
+                    SituationMatch::Recommend(directions) => {
+                        if let Some(direction) = directions[id]
+                            && moves[direction as usize]
+                        {
+                            move_matrix.set(id, MoveVector::from(direction));
+                        }
+                    }
+                    SituationMatch::Avoid(directions) => {
+                        if let Some(direction) = directions[id] {
+                            moves[direction as usize] = false;
+                            if moves.iter().any(|&valid| valid) {
+                                move_matrix.set(id, MoveVector::new(Some(moves)));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     /// Iterates through all situations and applies recommendations/avoidances.
     /// Returns `Some(Direction)` if a situation recommends an allowed direction, `None` otherwise.
     pub fn evaluate(
@@ -240,9 +315,11 @@ impl SituationSet {
     }
 }
 
+#[derive(Clone)]
 pub struct Situation {
     patterns: Vec<SituationPattern>,
     condition: Option<fn([Snake; 4]) -> bool>,
+    conditions: Vec<SnakeCondition>,
     score: i32,
     detail: String,
 }
@@ -284,12 +361,22 @@ impl Situation {
     }
 
     fn build(str: &str, result: SituationMatch, score: i32, detail: impl Into<String>) -> Self {
-        Self {
-            patterns: vec![SituationPattern::parse(str, result)],
+        Self::try_build(str, result, score, detail).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_build(
+        str: &str,
+        result: SituationMatch,
+        score: i32,
+        detail: impl Into<String>,
+    ) -> Result<Self, String> {
+        Ok(Self {
+            patterns: vec![SituationPattern::try_parse(str, result)?],
             condition: None,
+            conditions: Vec::new(),
             score,
             detail: detail.into(),
-        }
+        })
     }
 
     pub fn rotational(mut self) -> Self {
@@ -327,9 +414,22 @@ impl Situation {
     }
 
     pub fn check(&self, gamestate: &GameState<BasicField>) -> Option<SituationMatch> {
-        self.patterns.iter().find_map(|p| {
+        self.check_variant(gamestate).map(|(_, result)| result)
+    }
+
+    /// Like `check`, together with the index of the symmetry variant that matched first
+    pub fn check_variant(&self, gamestate: &GameState<BasicField>) -> Option<(usize, SituationMatch)> {
+        self.matches(gamestate).next()
+    }
+
+    /// Every symmetry variant that matches with its index, variants may match different snakes
+    pub fn matches<'a>(
+        &'a self,
+        gamestate: &'a GameState<BasicField>,
+    ) -> impl Iterator<Item = (usize, SituationMatch)> + 'a {
+        self.patterns.iter().enumerate().filter_map(move |(index, p)| {
             let (result, label_ids) = p.check(gamestate)?;
-            if let Some(condition) = self.condition {
+            if self.condition.is_some() || !self.conditions.is_empty() {
                 // Build ordered Snakes: slot 0 = own snake (A), slots 1/2/3 = B/C/D matched IDs.
                 // Unmatched labels get NonExistent.
                 let src = gamestate.snakes();
@@ -340,27 +440,50 @@ impl Situation {
                         ordered[slot + 1] = src.cell(*id).get();
                     }
                 }
-                if !condition(ordered) {
+                if self.condition.is_some_and(|condition| !condition(ordered)) {
+                    return None;
+                }
+                if !self.conditions.iter().all(|condition| condition.holds(&ordered)) {
                     return None;
                 }
             }
             // Remap from label-order [A,B,C,D] to gamestate-snake-order.
             // result[0] (A = own snake) stays at slot 0.
             // result[1..] (B/C/D) move to the slot of the matched gamestate snake ID.
-            Some(result.remap_to_gamestate(&label_ids))
+            Some((index, result.remap_to_gamestate(&label_ids)))
         })
     }
 
+    /// Number of distinct symmetry variants of the pattern
+    pub fn variants(&self) -> usize {
+        self.patterns.len()
+    }
+
     pub fn condition(mut self, condition: fn([Snake; 4]) -> bool) -> Self {
         self.condition = Some(condition);
         self
     }
+
+    /// Adds a declarative condition, all of them have to hold besides `condition`
+    pub fn when(mut self, condition: SnakeCondition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    pub fn detail(&self) -> &str {
+        &self.detail
+    }
+
+    pub fn score(&self) -> i32 {
+        self.score
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Situation, SituationSet};
+    use super::{Situation, SituationPattern, SituationSet};
     use crate::{
+        OriginalCoord,
         logic::general::{
             direction::Direction, evaluation::Evaluation, field::BasicField, game_state::GameState,
             snake::Snake,
@@ -555,6 +678,114 @@ mod tests {
         assert_eq!(situation.patterns.len(), 8);
     }
 
+    #[test]
+    fn test_extended_fields() {
+        let gamestate = read_game_state("requests/test_move_request.json");
+        let state = GameState::<BasicField>::from(&gamestate);
+        let situation = |pattern: &str| {
+            Situation::recommending(pattern, Direction::Up, 100, "Test")
+                .check(&state)
+                .is_some()
+        };
+
+        assert!(situation(
+            "
+            * * * * * +
+            * * * * * S
+            * * * H * *
+            A ~ * * * *
+            O T * * * *
+            "
+        ));
+        assert!(!situation(
+            "
+            * * * * * -
+            * * * * * *
+            * * * * * *
+            A * * * * *
+            "
+        ));
+        assert!(!situation(
+            "
+            . H
+            A .
+            "
+        ));
+        assert!(!situation(
+            "
+            A .
+            Z *
+            "
+        ));
+    }
+
+    #[test]
+    fn test_extended_fields_display_roundtrip() {
+        let situation = Situation::recommending(
+            "
+            + - = H
+            O S T Z
+            ~ A . X
+            ",
+            Direction::Up,
+            100,
+            "Test",
+        )
+        .full_symmetry();
+        assert_eq!(situation.patterns.len(), 8);
+        for pattern in &situation.patterns {
+            let display = pattern.to_string();
+            let (grid, _) = display.rsplit_once("=>").unwrap();
+            let parsed = SituationPattern::try_parse(grid, pattern.result).unwrap();
+            assert!(parsed == *pattern, "{}", display);
+        }
+    }
+
+    #[test]
+    fn test_restrict_opponents() {
+        let gamestate = read_game_state("requests/opponent_dead_end.json");
+        let state = GameState::<BasicField>::from(&gamestate);
+        let mut move_matrix = state.valid_moves();
+        assert_eq!(*move_matrix.get(1), Some([true, false, false, true]));
+
+        let situation_set = SituationSet::parse(include_str!("opponents.situations")).unwrap();
+        situation_set.restrict_opponents(&state, &mut move_matrix);
+        assert_eq!(*move_matrix.get(1), Some([false, false, false, true]));
+        assert_eq!(*move_matrix.get(0), *state.valid_moves().get(0));
+
+        // The variant mirrored along the diagonal finds a second opponent against the bottom wall
+        let coords = |coords: &[(i32, i32)]| -> Vec<OriginalCoord> {
+            coords.iter().map(|&(x, y)| OriginalCoord { x, y }).collect()
+        };
+        let mut gamestate = gamestate;
+        let template = gamestate.board.snakes[1].clone();
+        gamestate.board.snakes.clear();
+        for (id, body) in [
+            ("a", coords(&[(2, 2), (2, 3), (1, 3), (1, 4), (0, 4)])),
+            ("b", coords(&[(0, 2), (0, 1), (0, 0)])),
+            ("c", coords(&[(2, 0), (1, 0), (1, 1)])),
+            ("d", coords(&[(5, 0), (4, 0), (4, 1), (3, 1)])),
+        ] {
+            let mut snake = template.clone();
+            snake.id = id.to_string();
+            snake.head = body[0];
+            snake.length = body.len() as i32;
+            snake.body = body;
+            gamestate.board.snakes.push(snake);
+        }
+        gamestate.you = gamestate.board.snakes[0].clone();
+        let state = GameState::<BasicField>::from(&gamestate);
+        let situation = &situation_set.situations()[0];
+        assert_eq!(situation.matches(&state).count(), 2);
+        assert!(situation.check(&state).is_some());
+        let mut move_matrix = state.valid_moves();
+        assert_eq!(*move_matrix.get(1), Some([true, false, false, true]));
+        assert_eq!(*move_matrix.get(2), Some([true, false, false, true]));
+        situation_set.restrict_opponents(&state, &mut move_matrix);
+        assert_eq!(*move_matrix.get(1), Some([false, false, false, true]));
+        assert_eq!(*move_matrix.get(2), Some([true, false, false, false]));
+    }
+
     #[test]
     fn test_condition() {
         let gamestate = read_game_state("requests/test_move_request_2.json");
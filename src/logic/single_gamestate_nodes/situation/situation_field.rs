@@ -1,9 +1,24 @@
-use crate::logic::general::field::BasicField;
+use std::cmp::Ordering;
+
+use crate::logic::general::{
+    coord::Coord, field::BasicField, game_state::GameState, snake::Snake, snakes::SNAKES,
+};
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum SituationField {
     OwnHead,
     OtherHead(u8), // 0=B, 1=C, 2=D
+    /// Head of an opponent compared by length to our snake, not bound to a label
+    RankedHead(Ordering),
+    OwnBody,
+    OtherBody,
+    /// Tail that moves away next turn
+    MovingTail,
+    /// Tail that stays next turn because the snake just ate
+    StackedTail,
+    Hazard,
+    /// Empty, food or a tail that moves away next turn
+    FreeNextTurn,
     MovableArea,
     NonMovableArea,
     Wall,
@@ -12,18 +27,27 @@ pub enum SituationField {
 }
 
 impl SituationField {
-    pub fn from(c: char) -> Self {
+    pub fn parse(c: char) -> Option<Self> {
         match c {
-            'A' => Self::OwnHead,
-            'B' => Self::OtherHead(0),
-            'C' => Self::OtherHead(1),
-            'D' => Self::OtherHead(2),
-            '.' => Self::MovableArea,
-            'N' => Self::NonMovableArea,
-            'W' => Self::Wall,
-            '*' => Self::Any,
-            'X' => Self::Food,
-            _ => panic!("Invalid character for SituationField: {}", c),
+            'A' => Some(Self::OwnHead),
+            'B' => Some(Self::OtherHead(0)),
+            'C' => Some(Self::OtherHead(1)),
+            'D' => Some(Self::OtherHead(2)),
+            '+' => Some(Self::RankedHead(Ordering::Greater)),
+            '-' => Some(Self::RankedHead(Ordering::Less)),
+            '=' => Some(Self::RankedHead(Ordering::Equal)),
+            'O' => Some(Self::OwnBody),
+            'S' => Some(Self::OtherBody),
+            'T' => Some(Self::MovingTail),
+            'Z' => Some(Self::StackedTail),
+            'H' => Some(Self::Hazard),
+            '~' => Some(Self::FreeNextTurn),
+            '.' => Some(Self::MovableArea),
+            'N' => Some(Self::NonMovableArea),
+            'W' => Some(Self::Wall),
+            '*' => Some(Self::Any),
+            'X' => Some(Self::Food),
+            _ => None,
         }
     }
 
@@ -33,6 +57,15 @@ impl SituationField {
             Self::OtherHead(0) => 'B',
             Self::OtherHead(1) => 'C',
             Self::OtherHead(_) => 'D',
+            Self::RankedHead(Ordering::Greater) => '+',
+            Self::RankedHead(Ordering::Less) => '-',
+            Self::RankedHead(Ordering::Equal) => '=',
+            Self::OwnBody => 'O',
+            Self::OtherBody => 'S',
+            Self::MovingTail => 'T',
+            Self::StackedTail => 'Z',
+            Self::Hazard => 'H',
+            Self::FreeNextTurn => '~',
             Self::MovableArea => '.',
             Self::NonMovableArea => 'N',
             Self::Wall => 'W',
@@ -41,7 +74,20 @@ impl SituationField {
         }
     }
 
-    pub fn check(&self, field: Option<BasicField>) -> bool {
+    /// Stack of the snake whose tail is at `coord`, `None` if no tail is there
+    fn tail_stack(gamestate: &GameState<BasicField>, coord: Coord) -> Option<u8> {
+        (0..SNAKES as u8).find_map(|id| match gamestate.snakes().cell(id).get() {
+            Snake::Alive { tail, stack, .. } | Snake::Headless { tail, stack, .. }
+                if tail == coord =>
+            {
+                Some(stack)
+            }
+            _ => None,
+        })
+    }
+
+    pub fn check(&self, gamestate: &GameState<BasicField>, coord: Coord) -> bool {
+        let field = gamestate.board().cell_coord(coord).map(|c| c.get());
         match field {
             None => matches!(self, Self::NonMovableArea | Self::Wall),
             Some(f) => match self {
@@ -53,6 +99,31 @@ impl SituationField {
                         next: None
                     }
                 ),
+                Self::RankedHead(ordering) => match f {
+                    BasicField::Snake {
+                        id: id @ 1..=3,
+                        next: None,
+                    } => {
+                        let lengths = gamestate.snakes().lengths();
+                        lengths[id as usize].cmp(&lengths[0]) == *ordering
+                    }
+                    _ => false,
+                },
+                Self::OwnBody => matches!(f, BasicField::Snake { id: 0, next: Some(_) }),
+                Self::OtherBody => matches!(
+                    f,
+                    BasicField::Snake {
+                        id: 1..=3,
+                        next: Some(_)
+                    }
+                ),
+                Self::MovingTail => Self::tail_stack(gamestate, coord) == Some(0),
+                Self::StackedTail => Self::tail_stack(gamestate, coord).is_some_and(|s| s > 0),
+                Self::Hazard => gamestate.board().hazards().contains(coord),
+                Self::FreeNextTurn => match f {
+                    BasicField::Empty | BasicField::Food => true,
+                    BasicField::Snake { .. } => Self::tail_stack(gamestate, coord) == Some(0),
+                },
                 Self::MovableArea => matches!(f, BasicField::Empty | BasicField::Food),
                 Self::Food => matches!(f, BasicField::Food),
                 Self::NonMovableArea => matches!(f, BasicField::Snake { .. }),
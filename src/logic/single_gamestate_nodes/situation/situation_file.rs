@@ -0,0 +1,419 @@
+use super::{Situation, SituationMatch, SituationSet, situation_field::SituationField};
+use crate::logic::general::{direction::Direction, snake::Snake, snakes::SNAKES};
+use std::{fs, time::SystemTime};
+
+/// Value of a snake matched by a label of the pattern (A = own snake, B/C/D = other heads)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operand {
+    Length(usize),
+    Health(usize),
+    Value(i32),
+}
+
+impl Operand {
+    fn parse(token: &str) -> Result<Self, String> {
+        if let Ok(value) = token.parse() {
+            return Ok(Self::Value(value));
+        }
+        let (label, property) = token.split_once('.').unwrap_or((token, "length"));
+        let index = match label {
+            "A" => 0,
+            "B" => 1,
+            "C" => 2,
+            "D" => 3,
+            _ => return Err(format!("unknown snake label {}", label)),
+        };
+        match property {
+            "length" => Ok(Self::Length(index)),
+            "health" => Ok(Self::Health(index)),
+            _ => Err(format!("unknown snake property {}", property)),
+        }
+    }
+
+    fn value(&self, snakes: &[Snake; SNAKES]) -> Option<i32> {
+        match (self, self.snake(snakes)) {
+            (Self::Value(value), _) => Some(*value),
+            (Self::Length(_), Some(Snake::Alive { length, .. })) => Some(length as i32),
+            (Self::Health(_), Some(Snake::Alive { health, .. })) => Some(health as i32),
+            _ => None,
+        }
+    }
+
+    fn snake(&self, snakes: &[Snake; SNAKES]) -> Option<Snake> {
+        match self {
+            Self::Length(index) | Self::Health(index) => Some(snakes[*index]),
+            Self::Value(_) => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+/// Declarative condition on the matched snakes, e.g. `A > B` or `B.health <= 20`.
+/// A bare label compares the length, conditions on snakes that are not alive never hold.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SnakeCondition {
+    pub left: Operand,
+    pub comparison: Comparison,
+    pub right: Operand,
+}
+
+impl SnakeCondition {
+    pub fn parse(str: &str) -> Result<Self, String> {
+        let tokens: Vec<&str> = str.split_whitespace().collect();
+        let [left, comparison, right] = tokens[..] else {
+            return Err(format!("expected <operand> <comparison> <operand>, got {}", str));
+        };
+        let comparison = match comparison {
+            "<" => Comparison::Less,
+            "<=" => Comparison::LessOrEqual,
+            ">" => Comparison::Greater,
+            ">=" => Comparison::GreaterOrEqual,
+            "==" => Comparison::Equal,
+            "!=" => Comparison::NotEqual,
+            _ => return Err(format!("unknown comparison {}", comparison)),
+        };
+        Ok(Self {
+            left: Operand::parse(left)?,
+            comparison,
+            right: Operand::parse(right)?,
+        })
+    }
+
+    /// `snakes` are ordered by label, as passed to the conditions of a `Situation`
+    pub fn holds(&self, snakes: &[Snake; SNAKES]) -> bool {
+        let (Some(left), Some(right)) = (self.left.value(snakes), self.right.value(snakes)) else {
+            return false;
+        };
+        match self.comparison {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+        }
+    }
+}
+
+fn parse_directions(tokens: &[&str]) -> Result<[Option<Direction>; SNAKES], String> {
+    if tokens.is_empty() || tokens.len() > SNAKES {
+        return Err(format!("expected 1 to {} directions", SNAKES));
+    }
+    let mut directions = [None; SNAKES];
+    for (i, token) in tokens.iter().enumerate() {
+        directions[i] = match *token {
+            "-" => None,
+            _ => {
+                let mut chars = token.chars();
+                match (chars.next().map(Direction::try_from), chars.next()) {
+                    (Some(Ok(direction)), None) => Some(direction),
+                    _ => return Err(format!("unknown direction {}, expected U, D, L, R or -", token)),
+                }
+            }
+        };
+    }
+    Ok(directions)
+}
+
+/// A situation while it is being read, `line` is where it starts
+struct Draft {
+    line: usize,
+    detail: String,
+    result: Option<SituationMatch>,
+    score: i32,
+    symmetry: String,
+    conditions: Vec<SnakeCondition>,
+    pattern: Option<(usize, Vec<String>)>,
+}
+
+impl Draft {
+    fn finish(self) -> Result<Situation, String> {
+        let error = |message: String| format!("line {}: {}", self.line, message);
+        let result = self
+            .result
+            .ok_or_else(|| error(format!("situation {} has no recommend or avoid", self.detail)))?;
+        let (pattern_line, rows) = self
+            .pattern
+            .ok_or_else(|| error(format!("situation {} has no pattern", self.detail)))?;
+        let mut situation = Situation::try_build(&rows.join("\n"), result, self.score, self.detail)
+            .map_err(|e| format!("line {}: {}", pattern_line, e))?;
+        situation = match self.symmetry.as_str() {
+            "none" => situation,
+            "rotational" => situation.rotational(),
+            "mirrored" => situation.mirrored(),
+            "full" => situation.full_symmetry(),
+            other => return Err(error(format!("unknown symmetry {}", other))),
+        };
+        for condition in self.conditions {
+            situation = situation.when(condition);
+        }
+        Ok(situation)
+    }
+}
+
+/// Reads situations in the situation file format:
+///
+/// ```text
+/// # Comment
+/// situation Kill by Follow
+/// recommend U          # or `avoid U`, per label A B C D: `recommend U U - -`
+/// score 100
+/// symmetry full        # none (default), rotational, mirrored or full
+/// condition A > B      # lengths, also A.health, B.length and numbers
+/// pattern
+///     W B .
+///     W N A
+/// end
+/// ```
+///
+/// Errors name the line they were found on.
+pub fn parse_situations(text: &str) -> Result<Vec<Situation>, String> {
+    let mut situations = Vec::new();
+    let mut draft: Option<Draft> = None;
+    let mut in_pattern = false;
+
+    for (index, line) in text.lines().enumerate() {
+        let number = index + 1;
+        let error = |message: String| format!("line {}: {}", number, message);
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+
+        if keyword == "situation" {
+            if draft.is_some() {
+                return Err(error("situation started before the previous one ended".to_string()));
+            }
+            if rest.is_empty() {
+                return Err(error("situation needs a detail".to_string()));
+            }
+            draft = Some(Draft {
+                line: number,
+                detail: rest.to_string(),
+                result: None,
+                score: 0,
+                symmetry: "none".to_string(),
+                conditions: Vec::new(),
+                pattern: None,
+            });
+            continue;
+        }
+        let Some(current) = draft.as_mut() else {
+            return Err(error(format!("{} outside of a situation", keyword)));
+        };
+
+        if keyword == "end" {
+            in_pattern = false;
+            situations.push(draft.take().unwrap().finish()?);
+            continue;
+        }
+        if in_pattern {
+            for token in line.split_whitespace() {
+                let mut chars = token.chars();
+                match (chars.next().and_then(SituationField::parse), chars.next()) {
+                    (Some(_), None) => {}
+                    _ => return Err(error(format!("unknown pattern field {}", token))),
+                }
+            }
+            current.pattern.as_mut().unwrap().1.push(line.to_string());
+            continue;
+        }
+
+        let tokens: Vec<&str> = rest.split_whitespace().collect();
+        match keyword {
+            "recommend" => {
+                current.result = Some(SituationMatch::Recommend(
+                    parse_directions(&tokens).map_err(error)?,
+                ))
+            }
+            "avoid" => {
+                current.result = Some(SituationMatch::Avoid(
+                    parse_directions(&tokens).map_err(error)?,
+                ))
+            }
+            "score" => {
+                current.score = rest
+                    .parse()
+                    .map_err(|_| error(format!("invalid score {}", rest)))?
+            }
+            "symmetry" => current.symmetry = rest.to_string(),
+            "condition" => current
+                .conditions
+                .push(SnakeCondition::parse(rest).map_err(error)?),
+            "pattern" => {
+                in_pattern = true;
+                current.pattern = Some((number, Vec::new()));
+            }
+            _ => return Err(error(format!("unknown keyword {}", keyword))),
+        }
+    }
+
+    if let Some(draft) = draft {
+        return Err(format!(
+            "line {}: situation {} is not ended",
+            draft.line, draft.detail
+        ));
+    }
+    Ok(situations)
+}
+
+/// A situation file that is read again when it was modified since it was last read
+pub struct SituationFile {
+    path: String,
+    modified: Option<SystemTime>,
+    set: SituationSet,
+}
+
+impl SituationFile {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let mut file = Self {
+            path: path.to_string(),
+            modified: None,
+            set: SituationSet::new(Vec::new()),
+        };
+        file.reload_if_changed()?;
+        Ok(file)
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        fs::metadata(&self.path).and_then(|m| m.modified()).ok()
+    }
+
+    /// Reads the file again if its modification time changed, returns whether it was read.
+    /// On errors the previously read situations are kept.
+    pub fn reload_if_changed(&mut self) -> Result<bool, String> {
+        let modified = self.modified();
+        if self.modified.is_some() && modified == self.modified {
+            return Ok(false);
+        }
+        self.set = SituationSet::load(&self.path)?;
+        self.modified = modified;
+        Ok(true)
+    }
+
+    pub fn set(&self) -> &SituationSet {
+        &self.set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::general::coord::Coord;
+
+    fn alive(length: u8, health: u8) -> Snake {
+        Snake::Alive {
+            id: 0,
+            health,
+            length,
+            head: Coord::new(0, 0),
+            tail: Coord::new(0, 0),
+            stack: 0,
+        }
+    }
+
+    #[test]
+    fn test_condition() {
+        let snakes = [alive(5, 80), alive(4, 20), Snake::NonExistent, Snake::NonExistent];
+        assert!(SnakeCondition::parse("A > B").unwrap().holds(&snakes));
+        assert!(SnakeCondition::parse("B.health <= 20").unwrap().holds(&snakes));
+        assert!(!SnakeCondition::parse("A.length == 4").unwrap().holds(&snakes));
+        assert!(!SnakeCondition::parse("C < 100").unwrap().holds(&snakes));
+        assert!(SnakeCondition::parse("A >> B").is_err());
+        assert!(SnakeCondition::parse("E > B").is_err());
+    }
+
+    #[test]
+    fn test_fast_track_file() {
+        let parsed = parse_situations(include_str!("fast_track.situations")).unwrap();
+        let built = Situation::multi_recommending(
+            "
+            W . .
+            W A .
+            W N B
+            ",
+            [Some(Direction::Up), Some(Direction::Up), None, None],
+            0,
+            "Fast Track",
+        )
+        .full_symmetry();
+        assert_eq!(parsed.len(), 1);
+        assert!(parsed[0].patterns == built.patterns);
+        assert_eq!(parsed[0].conditions, vec![SnakeCondition::parse("A <= B").unwrap()]);
+        assert_eq!(parse_situations(include_str!("special.situations")).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |text: &str| parse_situations(text).err().unwrap();
+        assert_eq!(
+            error("situation Test\nrecommend U\npattern\n  A Q\nend\n"),
+            "line 4: unknown pattern field Q"
+        );
+        assert_eq!(
+            error("# header\n\nsituation Test\nrecommend U\npattern\n  . .\nend\n"),
+            "line 5: Situation must contain an OwnHead (A) field"
+        );
+        assert_eq!(
+            error("situation Test\npattern\n  A .\nend\n"),
+            "line 1: situation Test has no recommend or avoid"
+        );
+        assert_eq!(error("score 10\n"), "line 1: score outside of a situation");
+        assert_eq!(
+            error("situation Test\nrecommend X\n"),
+            "line 2: unknown direction X, expected U, D, L, R or -"
+        );
+        assert_eq!(
+            error("situation Test\nrecommend U\n"),
+            "line 1: situation Test is not ended"
+        );
+    }
+
+    #[test]
+    fn test_reload_if_changed() {
+        let path = std::env::temp_dir().join(format!("situations_{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        fs::write(path, "situation One\nrecommend U\npattern\n  A\nend\n").unwrap();
+        let mut file = SituationFile::load(path).unwrap();
+        assert_eq!(file.set().situations.len(), 1);
+        assert!(!file.reload_if_changed().unwrap());
+
+        fs::write(
+            path,
+            "situation One\nrecommend U\npattern\n  A\nend\nsituation Two\navoid D\npattern\n  A\nend\n",
+        )
+        .unwrap();
+        // Make sure the modification time differs on file systems with coarse timestamps
+        let later = SystemTime::now() + std::time::Duration::from_secs(5);
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert!(file.reload_if_changed().unwrap());
+        assert_eq!(file.set().situations.len(), 2);
+
+        fs::write(path, "situation Broken\n").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(later + std::time::Duration::from_secs(5))
+            .unwrap();
+        assert!(file.reload_if_changed().is_err());
+        assert_eq!(file.set().situations.len(), 2);
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,143 @@
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    hash::{Hash, Hasher},
+    ops::Index,
+};
+
+use rustc_hash::{FxHashMap, FxHasher};
+
+use crate::logic::{
+    general::{field::BasicField, game_state::GameState},
+    single_gamestate_nodes::node::{Node, NodeState, node_id::NodeId},
+};
+
+/// Nodes of a tree in one contiguous vector, looked up by id through an index. Ids are only
+/// kept by their nodes: the index maps the hash of an id to its slot, and an id whose hash
+/// already belongs to another id goes to `collisions`.
+#[derive(Clone)]
+pub(crate) struct Arena<S = GameState<BasicField>> {
+    nodes: Vec<Node<S>>,
+    index: FxHashMap<u64, u32>,
+    collisions: HashMap<NodeId, u32>,
+}
+
+impl<S> Default for Arena<S> {
+    fn default() -> Self {
+        Self {
+            nodes: Vec::new(),
+            index: FxHashMap::default(),
+            collisions: HashMap::new(),
+        }
+    }
+}
+
+fn hash(id: &NodeId) -> u64 {
+    let mut hasher = FxHasher::default();
+    id.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl<S: NodeState> Arena<S> {
+    fn slot(&self, id: &NodeId) -> Option<usize> {
+        let slot = *self.index.get(&hash(id))? as usize;
+        if self.nodes[slot].id_ref() == id {
+            return Some(slot);
+        }
+        self.collisions.get(id).map(|&slot| slot as usize)
+    }
+
+    pub fn get(&self, id: &NodeId) -> Option<&Node<S>> {
+        self.slot(id).map(|slot| &self.nodes[slot])
+    }
+
+    pub fn get_mut(&mut self, id: &NodeId) -> Option<&mut Node<S>> {
+        self.slot(id).map(|slot| &mut self.nodes[slot])
+    }
+
+    /// Adds a node, replacing the node with the same id
+    pub fn insert(&mut self, id: NodeId, node: Node<S>) {
+        debug_assert!(node.id_ref() == &id, "Node {} inserted as {}", node.id_ref(), id);
+        if let Some(slot) = self.slot(&id) {
+            self.nodes[slot] = node;
+            return;
+        }
+        let slot = self.nodes.len() as u32;
+        match self.index.entry(hash(&id)) {
+            Entry::Vacant(entry) => {
+                entry.insert(slot);
+            }
+            Entry::Occupied(_) => {
+                self.collisions.insert(id, slot);
+            }
+        }
+        self.nodes.push(node);
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &NodeId> {
+        self.nodes.iter().map(Node::id_ref)
+    }
+
+    /// Bytes held by the arena, including unused capacity
+    pub fn memory_bytes(&self) -> usize {
+        self.nodes.capacity() * std::mem::size_of::<Node<S>>()
+            + self.index.capacity() * (std::mem::size_of::<u64>() + std::mem::size_of::<u32>())
+            + self.collisions.capacity()
+                * (std::mem::size_of::<NodeId>() + std::mem::size_of::<u32>())
+            + self.nodes.iter().map(Node::heap_bytes).sum::<usize>()
+    }
+}
+
+impl<S: NodeState> Index<&NodeId> for Arena<S> {
+    type Output = Node<S>;
+
+    fn index(&self, id: &NodeId) -> &Node<S> {
+        self.get(id).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        logic::general::{direction::Direction::*, field::BasicField, game_state::GameState},
+        read_game_state,
+    };
+
+    #[test]
+    fn test_arena() {
+        let gamestate = GameState::<BasicField>::from(&read_game_state("requests/failure_1.json"));
+        let root = NodeId::new();
+        let child = root.child([Some(Up), None, None, None]);
+        let mut arena: Arena = Arena::default();
+        arena.insert(root.clone(), Node::new(root.clone(), gamestate.clone()));
+        arena.insert(child.clone(), Node::new(child.clone(), gamestate.clone()));
+        arena.insert(child.clone(), Node::new(child.clone(), gamestate));
+        assert_eq!(arena.len(), 2);
+        assert!(arena.get(&child).unwrap().id() == child);
+        assert!(arena.get(&root.child([Some(Down), None, None, None])).is_none());
+        assert_eq!(arena.keys().filter(|&id| arena[id].id() == *id).count(), 2);
+        assert!(arena.memory_bytes() > 2 * std::mem::size_of::<Node>());
+    }
+
+    #[test]
+    fn test_arena_hash_collision() {
+        let gamestate = GameState::<BasicField>::from(&read_game_state("requests/failure_1.json"));
+        let root = NodeId::new();
+        let child = root.child([Some(Up), None, None, None]);
+        let mut arena: Arena = Arena::default();
+        arena.insert(root.clone(), Node::new(root.clone(), gamestate.clone()));
+        // Let the child's hash point at the root's slot
+        arena.index.insert(hash(&child), 0);
+        arena.insert(child.clone(), Node::new(child.clone(), gamestate.clone()));
+        arena.insert(child.clone(), Node::new(child.clone(), gamestate));
+        assert_eq!(arena.len(), 2);
+        assert_eq!(arena.collisions.len(), 1);
+        assert!(arena[&root].id() == root);
+        assert!(arena[&child].id() == child);
+        assert!(arena.get(&root.child([Some(Down), None, None, None])).is_none());
+    }
+}
@@ -0,0 +1,160 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use crate::logic::single_gamestate_nodes::node::node_id::NodeId;
+
+#[derive(Clone)]
+struct Entry {
+    id: NodeId,
+    fast_track: bool,
+    priority: i32,
+    sequence: u64,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    /// Greater entries are popped first
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.fast_track, other.fast_track) {
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            // The latest fast tracked node first, following a fast track line to its end
+            (true, true) => self.sequence.cmp(&other.sequence),
+            (false, false) => self
+                .priority
+                .cmp(&other.priority)
+                .then_with(|| other.id.depth().cmp(&self.id.depth()))
+                .then_with(|| other.sequence.cmp(&self.sequence)),
+        }
+    }
+}
+
+/// Nodes waiting to be simulated. Fast tracked nodes come first, then the highest priority,
+/// then the shallowest and earliest pushed node, so equal priorities explore breadth first.
+#[derive(Clone)]
+pub(crate) struct Frontier {
+    heap: BinaryHeap<Entry>,
+    sequence: u64,
+}
+
+impl Frontier {
+    pub(super) fn new() -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+            sequence: 0,
+        }
+    }
+
+    pub(super) fn from(id: NodeId) -> Self {
+        let mut frontier = Self::new();
+        frontier.push(id, 0);
+        frontier
+    }
+
+    fn insert(&mut self, id: NodeId, fast_track: bool, priority: i32) {
+        self.sequence += 1;
+        self.heap.push(Entry {
+            id,
+            fast_track,
+            priority,
+            sequence: self.sequence,
+        });
+    }
+
+    pub(super) fn push(&mut self, id: NodeId, priority: i32) {
+        self.insert(id, false, priority);
+    }
+
+    pub(super) fn push_front(&mut self, id: NodeId) {
+        self.insert(id, true, 0);
+    }
+
+    pub(super) fn pop(&mut self) -> Option<NodeId> {
+        self.heap.pop().map(|entry| entry.id)
+    }
+
+    /// Pops the next node after scoring it again with `priority_of`. A node whose priority
+    /// dropped since it was pushed goes back with the new priority, so stale priorities do not
+    /// jump the queue. Fast tracked nodes are not scored.
+    pub(super) fn pop_rescored(&mut self, mut priority_of: impl FnMut(&NodeId) -> i32) -> Option<NodeId> {
+        while let Some(entry) = self.heap.pop() {
+            if entry.fast_track {
+                return Some(entry.id);
+            }
+            let priority = priority_of(&entry.id);
+            if priority >= entry.priority {
+                return Some(entry.id);
+            }
+            self.heap.push(Entry { priority, ..entry });
+        }
+        None
+    }
+
+    #[allow(dead_code)]
+    pub(super) fn len(&self) -> usize {
+        self.heap.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::general::direction::Direction::*;
+
+    #[test]
+    fn test_frontier_order() {
+        let root = NodeId::new();
+        let a = root.child([Some(Up), None, None, None]);
+        let b = root.child([Some(Down), None, None, None]);
+        let deep = a.child([Some(Left), None, None, None]);
+
+        // Equal priorities are breadth first and first in, first out
+        let mut frontier = Frontier::from(root.clone());
+        frontier.push(deep.clone(), 0);
+        frontier.push(a.clone(), 0);
+        frontier.push(b.clone(), 0);
+        let order: Vec<NodeId> = std::iter::from_fn(|| frontier.pop()).collect();
+        assert!(order == vec![root.clone(), a.clone(), b.clone(), deep.clone()]);
+
+        // Priorities come before depth, fast tracked nodes before everything, latest first
+        let mut frontier = Frontier::new();
+        frontier.push(a.clone(), 0);
+        frontier.push(deep.clone(), 5);
+        frontier.push_front(b.clone());
+        frontier.push_front(root.clone());
+        assert_eq!(frontier.len(), 4);
+        let order: Vec<NodeId> = std::iter::from_fn(|| frontier.pop()).collect();
+        assert!(order == vec![root, b, deep, a]);
+    }
+
+    #[test]
+    fn test_frontier_rescoring() {
+        let root = NodeId::new();
+        let a = root.child([Some(Up), None, None, None]);
+        let b = root.child([Some(Down), None, None, None]);
+        let c = root.child([Some(Left), None, None, None]);
+
+        // a was pushed with 5 but scores 1 by now, so b comes first, a keeps its place before c
+        let mut frontier = Frontier::new();
+        frontier.push(a.clone(), 5);
+        frontier.push(b.clone(), 3);
+        frontier.push(c.clone(), 1);
+        frontier.push_front(root.clone());
+        let priority_of = |id: &NodeId| if *id == a { 1 } else { 3 };
+        let order: Vec<NodeId> =
+            std::iter::from_fn(|| frontier.pop_rescored(priority_of)).collect();
+        assert!(order == vec![root, b, a, c]);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::logic::{
+    general::{
+        direction::{DIRECTIONS, Direction},
+        snakes::SNAKES,
+    },
+    single_gamestate_nodes::node::{Node, NodeState, NodeStatus, node_id::NodeId},
+};
+
+use super::Tree;
+
+fn alive_opponents<S: NodeState>(node: &Node<S>) -> usize {
+    (1..SNAKES as u8).filter(|&id| node.is_alive(id)).count()
+}
+
+impl<S: NodeState> Tree<S> {
+    /// Plies until an opponent dies against all of its replies while we survive, per root
+    /// direction. Propagated like `DeadIn`: we pick our fastest kill, the opponents pick their
+    /// slowest. Replies that were not simulated, were removed by a move restriction or lead to our
+    /// death count as escapes.
+    pub fn kill_result(&self) -> [Option<u8>; 4] {
+        let root_id = NodeId::new();
+        let root = self.nodes.get(&root_id).unwrap();
+        let opponents = alive_opponents(root);
+        DIRECTIONS.map(|direction| self.direction_kill(&root_id, opponents, direction))
+    }
+
+    fn direction_kill(&self, node_id: &NodeId, opponents: usize, direction: Direction) -> Option<u8> {
+        let node = self.nodes.get(node_id)?;
+        let children = node.children()[direction as usize].take()?;
+        let spawned = children.len();
+        let mut slowest = None;
+        for (child_id, status) in children {
+            if matches!(status, NodeStatus::PrunedForSimilarity) {
+                continue;
+            }
+            let kill = self.node_kill(child_id, opponents)?;
+            slowest = slowest.max(Some(kill + 1));
+        }
+        // Replies removed by a move restriction were never spawned, so they are escapes as well
+        if self.move_restriction_fn.is_some() && spawned < self.unrestricted_replies(node_id)? {
+            return None;
+        }
+        slowest
+    }
+
+    fn unrestricted_replies(&self, node_id: &NodeId) -> Option<usize> {
+        let move_matrix = self.gamestate(node_id)?.valid_moves();
+        Some((1..SNAKES).map(|id| move_matrix.get(id).count_valid(1)).product())
+    }
+
+    fn node_kill(&self, node_id: NodeId, parent_opponents: usize) -> Option<u8> {
+        let node = self.nodes.get(&node_id)?;
+        if !node.is_alive(0) {
+            return None;
+        }
+        let opponents = alive_opponents(node);
+        if opponents < parent_opponents {
+            return Some(0);
+        }
+        DIRECTIONS
+            .into_iter()
+            .filter_map(|direction| self.direction_kill(&node_id, opponents, direction))
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::create_tree_from_gamestate;
+    use super::*;
+
+    #[test]
+    fn test_kill_result() {
+        // Right wins the head-to-head or corners the shorter opponent, which cannot escape
+        let mut tree = create_tree_from_gamestate("requests/failure_46_go_for_kill.json")
+            .all_root_directions()
+            .dead_ancestor_pruning()
+            .kill_seeking(3)
+            .max_depth(4);
+        tree.simulate();
+        assert_eq!(tree.kill_result(), [None, None, None, Some(2)]);
+
+        // Without seeking kills the tree stops at the first surviving reply
+        let mut tree = create_tree_from_gamestate("requests/failure_46_go_for_kill.json")
+            .all_root_directions()
+            .max_depth(4);
+        tree.simulate();
+        assert_eq!(tree.kill_result(), [None; 4]);
+
+        // Restricting the opponents to their first valid move hides the escape from Down, and the
+        // kill by Right cannot be confirmed without the removed replies either
+        let mut tree = create_tree_from_gamestate("requests/failure_46_go_for_kill.json")
+            .all_root_directions()
+            .dead_ancestor_pruning()
+            .kill_seeking(3)
+            .max_depth(4)
+            .restrict_moves(|_, move_matrix| {
+                for id in 1..4 {
+                    if let Some(moves) = *move_matrix.get(id) {
+                        let first = moves.iter().position(|&valid| valid).unwrap();
+                        move_matrix.set(id, Direction::try_from(first).unwrap().into());
+                    }
+                }
+            });
+        tree.simulate();
+        assert_eq!(tree.kill_result(), [None; 4]);
+
+        let mut tree = create_tree_from_gamestate("requests/failure_1.json")
+            .kill_seeking(2)
+            .max_depth(3);
+        tree.simulate();
+        assert_eq!(tree.kill_result(), [None; 4]);
+    }
+}
@@ -1,5 +1,6 @@
 use std::{
-    collections::{BTreeMap, HashMap, VecDeque},
+    borrow::Cow,
+    collections::HashMap,
     fmt,
     rc::Rc,
     time::{Duration, Instant},
@@ -7,34 +8,75 @@ use std::{
 
 use log::{debug, trace};
 
+mod arena;
+mod frontier;
+mod kills;
+pub mod priority;
 mod tree_stats;
 
+use arena::Arena;
+use frontier::Frontier;
+
 use crate::logic::{
-    general::{direction::Direction, field::BasicField, game_state::GameState},
-    single_gamestate_nodes::node::{Node, NodeStatus, QueueStatus, node_id::NodeId},
+    general::{direction::Direction, field::BasicField, game_state::GameState, moves::MoveMatrix},
+    single_gamestate_nodes::{
+        node::{
+            CompactState, FastTrackFn, MoveRestrictionFn, Node, NodeState, NodeStatus,
+            QueueStatus, Storage, node_id::NodeId,
+        },
+        situation::SituationSet,
+    },
+    watchdog,
 };
 
 #[derive(Clone)]
-pub struct Tree {
-    pub(super) nodes: HashMap<NodeId, Node>,
-    pub(super) queue: DepthQueue,
+pub struct Tree<S = GameState<BasicField>> {
+    pub(super) nodes: Arena<S>,
+    pub(super) queue: Frontier,
     pub(super) elapsed: Duration,
-    max_depth: u8,
+    max_depth: u16,
     max_time: Option<Duration>,
     max_nodes: usize,
     dead_ancestor_pruning: bool,
     all_root_directions: bool,
-    similarity_distance_fn: Option<fn(u8) -> u8>,
-    fast_track_fn: Option<Rc<dyn Fn(&Node) -> bool>>,
+    kill_depth: u16,
+    storage: Storage,
+    similarity_distance_fn: Option<fn(u16) -> u8>,
+    fast_track_fn: Option<Rc<FastTrackFn<S>>>,
+    move_restriction_fn: Option<Rc<MoveRestrictionFn<S>>>,
+    priority_fn: Option<Rc<PriorityFn<S>>>,
+    /// Gamestate recomputed to score the node popped last, reused to simulate it
+    scored_gamestate: Option<(NodeId, GameState<BasicField>)>,
 }
 
+/// Orders the nodes waiting to be simulated, see `tree::priority`. Gets the full gamestate of
+/// the node, which the node itself may keep compactly.
+pub type PriorityFn<S = GameState<BasicField>> =
+    dyn Fn(&Tree<S>, &Node<S>, &GameState<BasicField>) -> i32;
+
 impl Tree {
-    pub const MAX_DEPTH: u8 = NodeId::MAX_DEPTH;
+    pub const MAX_DEPTH: u16 = NodeId::MAX_DEPTH;
 
+    /// Tree whose nodes keep their full gamestate, see `Storage::Full`
     pub fn new(root: GameState<BasicField>) -> Self {
+        Self::with_storage(root, Storage::Full)
+    }
+}
+
+impl Tree<CompactState> {
+    /// Tree whose nodes keep their gamestates as `storage` asks for. Callbacks still get nodes
+    /// with their full gamestate.
+    pub fn compact(root: GameState<BasicField>, storage: Storage) -> Self {
+        Self::with_storage(root, storage)
+    }
+}
+
+impl<S: NodeState> Tree<S> {
+    fn with_storage(root: GameState<BasicField>, storage: Storage) -> Self {
         let node = Node::new(NodeId::new(), root);
-        let queue = DepthQueue::from(node.id());
-        let nodes = HashMap::from([(node.id(), node)]);
+        let queue = Frontier::from(node.id());
+        let mut nodes = Arena::default();
+        nodes.insert(node.id(), node);
         Self {
             nodes,
             queue,
@@ -44,12 +86,17 @@ impl Tree {
             elapsed: Duration::ZERO,
             dead_ancestor_pruning: false,
             all_root_directions: false,
+            kill_depth: 0,
+            storage,
             similarity_distance_fn: None,
             fast_track_fn: None,
+            move_restriction_fn: None,
+            priority_fn: None,
+            scored_gamestate: None,
         }
     }
 
-    pub fn max_depth(mut self, max_depth: u8) -> Self {
+    pub fn max_depth(mut self, max_depth: u16) -> Self {
         self.max_depth = max_depth;
         self
     }
@@ -69,7 +116,7 @@ impl Tree {
         self
     }
 
-    pub fn similarity_pruning(mut self, distance_fn: fn(u8) -> u8) -> Self {
+    pub fn similarity_pruning(mut self, distance_fn: fn(u16) -> u8) -> Self {
         self.similarity_distance_fn = Some(distance_fn);
         self
     }
@@ -79,11 +126,114 @@ impl Tree {
         self
     }
 
-    pub fn fast_track(mut self, fast_track_fn: impl Fn(&Node) -> bool + 'static) -> Self {
+    /// Simulates all our directions of nodes above `depth` instead of stopping at the first
+    /// surviving one, so `kill_result` finds kills within `depth` plies
+    pub fn kill_seeking(mut self, depth: u16) -> Self {
+        self.kill_depth = depth;
+        self
+    }
+
+    pub fn fast_track(mut self, fast_track_fn: impl Fn(&Node<S>) -> bool + 'static) -> Self {
         self.fast_track_fn = Some(Rc::new(fast_track_fn));
         self
     }
 
+    /// Lets `move_restriction_fn` narrow down the opponents' valid moves of a node before its
+    /// children are spawned. Our own moves are never restricted.
+    pub fn restrict_moves(
+        mut self,
+        move_restriction_fn: impl Fn(&Node<S>, &mut MoveMatrix) + 'static,
+    ) -> Self {
+        self.move_restriction_fn = Some(Rc::new(move_restriction_fn));
+        self
+    }
+
+    /// Simulates queued nodes with a higher priority first instead of breadth first.
+    /// The priority of a node is computed when it is queued and again when it is next, a node
+    /// whose priority dropped meanwhile is queued again. Equal priorities stay breadth first and
+    /// fast tracked nodes still come first.
+    pub fn priority(
+        mut self,
+        priority_fn: impl Fn(&Tree<S>, &Node<S>, &GameState<BasicField>) -> i32 + 'static,
+    ) -> Self {
+        self.priority_fn = Some(Rc::new(priority_fn));
+        self
+    }
+
+    fn priority_of(&self, node: &Node<S>, gamestate: &GameState<BasicField>) -> i32 {
+        self.priority_fn
+            .as_ref()
+            .map_or(0, |priority_fn| priority_fn(self, node, gamestate))
+    }
+
+    /// Queues a node that was queued before, by now it may keep its gamestate compactly
+    fn queue_node(&mut self, node_id: NodeId) {
+        let priority = match self.priority_fn {
+            Some(_) => {
+                let gamestate = self.gamestate(&node_id).unwrap();
+                self.priority_of(&self.nodes[&node_id], &gamestate)
+            }
+            None => 0,
+        };
+        self.queue.push(node_id, priority);
+    }
+
+    /// Restricts the opponents' moves by `situation_set` below the root, see
+    /// `SituationSet::restrict_opponents`. Applies after a restriction set by `restrict_moves`.
+    pub fn situation_restrictions(mut self, situation_set: SituationSet) -> Self {
+        let previous = self.move_restriction_fn.take();
+        self.move_restriction_fn = Some(Rc::new(move |node: &Node<S>, move_matrix: &mut MoveMatrix| {
+            if let Some(previous) = &previous {
+                previous(node, move_matrix);
+            }
+            if node.id().depth() > 0 {
+                situation_set.restrict_opponents(node.gamestate(), move_matrix);
+            }
+        }));
+        self
+    }
+
+    /// Simulates the opponents' replies that `situation_set` rules out below the root after the
+    /// others instead of removing them like `situation_restrictions`. Applies on top of a
+    /// priority set by `priority`. Checks the gamestate of the parent, which compact trees
+    /// recompute.
+    pub fn situation_priorities(mut self, situation_set: SituationSet) -> Self {
+        let previous = self.priority_fn.take();
+        self.priority_fn = Some(Rc::new(
+            move |tree: &Tree<S>, node: &Node<S>, gamestate: &GameState<BasicField>| {
+                previous.as_ref().map_or(0, |previous| previous(tree, node, gamestate))
+                    + priority::implausible_replies(tree, node, &situation_set)
+            },
+        ));
+        self
+    }
+
+    /// Gamestate of a node in any storage, recomputed from its parent if it keeps no board. The
+    /// parent was simulated and keeps one, so at most one move is replayed.
+    pub fn gamestate(&self, node_id: &NodeId) -> Option<Cow<'_, GameState<BasicField>>> {
+        let node = self.nodes.get(node_id)?;
+        if node.is_unpacked() {
+            return Some(Cow::Borrowed(node.gamestate()));
+        }
+        if let Some(gamestate) = node.unpacked() {
+            return Some(Cow::Owned(gamestate));
+        }
+        let mut gamestate = self.gamestate(&node_id.parent()?)?.into_owned();
+        gamestate.next_state(node_id.last_directions()?);
+        Some(Cow::Owned(gamestate))
+    }
+
+    fn unpack(&mut self, node_id: &NodeId) {
+        if self.nodes.get(node_id).unwrap().is_unpacked() {
+            return;
+        }
+        let gamestate = match self.scored_gamestate.take() {
+            Some((scored_id, gamestate)) if scored_id == *node_id => gamestate,
+            _ => self.gamestate(node_id).unwrap().into_owned(),
+        };
+        self.nodes.get_mut(node_id).unwrap().unpack(gamestate);
+    }
+
     pub fn result(&self) -> [NodeStatus; 4] {
         let root = self.nodes.get(&NodeId::new()).unwrap();
         [
@@ -101,16 +251,20 @@ impl Tree {
 
         if self.all_root_directions {
             let root_id = self.queue.pop().unwrap();
-            while self.simulate_node(root_id) {
+            while self.simulate_node(&root_id) {
                 // Keep simulating the root until all directions are exhausted. This ensures we have status information for all root directions, which is important for testing and debugging, even if we won't explore all of them in a real simulation due to time/depth constraints.
             }
         }
 
-        while let Some(node_id) = self.queue.pop() {
+        while let Some(node_id) = self.next_node() {
             if deadline.is_some_and(|d| Instant::now() >= d) {
                 debug!("Reached time limit, stopping simulation");
                 break;
             }
+            if watchdog::stop_requested() {
+                debug!("Move no longer awaited, stopping simulation");
+                break;
+            }
             if self.nodes.len() >= self.max_nodes {
                 debug!("Reached node limit, stopping simulation");
                 break;
@@ -121,14 +275,14 @@ impl Tree {
                     .get_mut(&node_id)
                     .unwrap()
                     .pin_status(NodeStatus::PrunedMaxDepth);
-                self.propagate_status(node_id, NodeStatus::PrunedMaxDepth);
+                self.propagate_status(&node_id, NodeStatus::PrunedMaxDepth);
                 continue;
             }
             let node_status = self.nodes.get(&node_id).unwrap().status();
             if self.dead_ancestor_pruning
                 && !matches!(node_status, NodeStatus::DeadIn(_))
                 && let Some((ancestor_id, ancestor_direction_status, direction)) =
-                    self.dead_ancestor_direction(node_id)
+                    self.dead_ancestor_direction(&node_id)
             {
                 debug!(
                     "Pruning {} as ancestor {} has direction status {} for direction {}",
@@ -138,36 +292,61 @@ impl Tree {
                     .get_mut(&node_id)
                     .unwrap()
                     .pin_status(NodeStatus::PrunedDeadAncestor);
-                self.propagate_status(node_id, NodeStatus::PrunedDeadAncestor);
+                self.propagate_status(&node_id, NodeStatus::PrunedDeadAncestor);
                 if let Some(parent_id) = node_id.parent() {
                     trace!(
                         "Adding parent {} to the queue for dead ancestor pruning",
                         parent_id
                     );
-                    self.queue.push(parent_id);
+                    self.queue_node(parent_id);
                 }
                 continue;
             }
-            self.simulate_node(node_id);
+            self.simulate_node(&node_id);
         }
         self.elapsed = start.elapsed();
     }
 
-    fn simulate_node(&mut self, node_id: NodeId) -> bool {
+    fn next_node(&mut self) -> Option<NodeId> {
+        if self.priority_fn.is_none() {
+            return self.queue.pop();
+        }
+        let mut queue = std::mem::replace(&mut self.queue, Frontier::new());
+        let mut scored = None;
+        let next = queue.pop_rescored(|node_id| {
+            let gamestate = self.gamestate(node_id).unwrap();
+            let priority = self.priority_of(&self.nodes[node_id], &gamestate);
+            if let Cow::Owned(gamestate) = gamestate {
+                scored = Some((node_id.clone(), gamestate));
+            }
+            priority
+        });
+        self.queue = queue;
+        self.scored_gamestate = scored.filter(|(node_id, _)| Some(node_id) == next.as_ref());
+        next
+    }
+
+    fn simulate_node(&mut self, node_id: &NodeId) -> bool {
         debug!("Simulating {}", node_id);
         let similarity_distance = self
             .similarity_distance_fn
             .as_ref()
             .map(|f| f(node_id.depth()));
-        let node = self.nodes.get_mut(&node_id).unwrap();
-        let simulation_result = node.simulate(similarity_distance, self.fast_track_fn.as_deref());
+        self.unpack(node_id);
+        let node = self.nodes.get_mut(node_id).unwrap();
+        let simulation_result = node.simulate(
+            similarity_distance,
+            self.fast_track_fn.as_deref(),
+            self.move_restriction_fn.as_deref(),
+        );
+        node.pack(self.storage, true);
         let node_status = node.status();
         let node_queue_status = node.read_queue_status();
         self.propagate_status(node_id, node_status);
         match simulation_result {
             Some(children) => {
                 debug!("{} has spawned {} children", node_id, children.len());
-                for child in children {
+                for mut child in children {
                     let child_id = child.id();
                     if matches!(
                         child.read_queue_status(),
@@ -177,13 +356,19 @@ impl Tree {
                             "Fast Tracked: Adding child {} to the front of queue",
                             child_id
                         );
-                        self.queue.push_front(child_id);
+                        self.queue.push_front(child_id.clone());
                     } else {
                         trace!("Adding child {} to the queue", child_id);
-                        self.queue.push(child_id);
+                        let priority = self.priority_of(&child, child.gamestate());
+                        self.queue.push(child_id.clone(), priority);
                     }
+                    child.pack(self.storage, false);
                     self.nodes.insert(child_id, child);
                 }
+                if node_id.depth() < self.kill_depth {
+                    trace!("Adding {} to the queue to seek kills", node_id);
+                    self.queue_node(node_id.clone());
+                }
                 true
             }
             None => {
@@ -203,7 +388,7 @@ impl Tree {
                         self.queue.push_front(parent_id);
                     } else {
                         trace!("Adding parent {} to the queue", parent_id);
-                        self.queue.push(parent_id);
+                        self.queue_node(parent_id);
                     }
                 }
                 false
@@ -211,8 +396,8 @@ impl Tree {
         }
     }
 
-    fn propagate_status(&mut self, node_id: NodeId, node_status: NodeStatus) {
-        let mut changing_node_id = node_id;
+    fn propagate_status(&mut self, node_id: &NodeId, node_status: NodeStatus) {
+        let mut changing_node_id = node_id.clone();
         let mut node_status = node_status;
         while let Some(parent_id) = changing_node_id.parent() {
             trace!(
@@ -220,10 +405,10 @@ impl Tree {
                 node_status, parent_id
             );
             let parent = self.nodes.get_mut(&parent_id).unwrap();
-            if parent.propagate_update_from_child(changing_node_id, node_status) {
-                changing_node_id = parent_id;
+            if parent.propagate_update_from_child(&changing_node_id, node_status) {
                 node_status = parent.status();
                 trace!("Status for {} updated to {}", parent_id, node_status);
+                changing_node_id = parent_id;
             } else {
                 trace!("Status for {} unchanged {}", parent_id, parent.status());
                 break;
@@ -231,8 +416,8 @@ impl Tree {
         }
     }
 
-    fn dead_ancestor_direction(&self, node_id: NodeId) -> Option<(NodeId, NodeStatus, Direction)> {
-        let mut id = node_id;
+    fn dead_ancestor_direction(&self, node_id: &NodeId) -> Option<(NodeId, NodeStatus, Direction)> {
+        let mut id = node_id.clone();
         while let Some(parent_id) = id.parent() {
             if let Some(parent) = self.nodes.get(&parent_id) {
                 let direction = id.last_direction_for(0).unwrap().unwrap();
@@ -250,20 +435,20 @@ impl Tree {
     }
 }
 
-impl fmt::Display for Tree {
+impl<S: NodeState> fmt::Display for Tree<S> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // Build parent -> children map and group nodes by depth
         let mut children: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
-        let mut by_depth: HashMap<u8, Vec<NodeId>> = HashMap::new();
-        for &id in self.nodes.keys() {
+        let mut by_depth: HashMap<u16, Vec<NodeId>> = HashMap::new();
+        for id in self.nodes.keys() {
             if let Some(parent_id) = id.parent() {
-                children.entry(parent_id).or_default().push(id);
+                children.entry(parent_id).or_default().push(id.clone());
             }
-            by_depth.entry(id.depth()).or_default().push(id);
+            by_depth.entry(id.depth()).or_default().push(id.clone());
         }
 
         // Count descendants bottom-up
-        let mut depths: Vec<u8> = by_depth.keys().copied().collect();
+        let mut depths: Vec<u16> = by_depth.keys().copied().collect();
         depths.sort();
         let mut descendants: HashMap<NodeId, usize> = HashMap::new();
         for &depth in depths.iter().rev() {
@@ -276,7 +461,7 @@ impl fmt::Display for Tree {
                             .sum()
                     })
                     .unwrap_or(0);
-                descendants.insert(*id, child_count);
+                descendants.insert(id.clone(), child_count);
             }
         }
 
@@ -307,50 +492,6 @@ impl fmt::Display for Tree {
     }
 }
 
-#[derive(Clone)]
-pub(super) struct DepthQueue {
-    buckets: BTreeMap<u8, VecDeque<NodeId>>,
-}
-
-impl DepthQueue {
-    fn new() -> Self {
-        Self {
-            buckets: BTreeMap::new(),
-        }
-    }
-
-    fn from(id: NodeId) -> Self {
-        let mut q = Self::new();
-        q.push(id);
-        q
-    }
-
-    fn push(&mut self, id: NodeId) {
-        self.buckets.entry(id.depth()).or_default().push_back(id);
-    }
-
-    fn push_front(&mut self, id: NodeId) {
-        self.buckets.entry(0).or_default().push_front(id);
-    }
-
-    fn pop(&mut self) -> Option<NodeId> {
-        let (&depth, queue) = self.buckets.iter_mut().next()?;
-        let id = queue.pop_front();
-        if queue.is_empty() {
-            self.buckets.remove(&depth);
-        }
-        id
-    }
-
-    fn is_empty(&self) -> bool {
-        self.buckets.is_empty()
-    }
-
-    pub(super) fn len(&self) -> usize {
-        self.buckets.values().map(|q| q.len()).sum()
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use log::info;
@@ -359,7 +500,7 @@ mod tests {
     use crate::{
         logic::{
             general::{direction::DIRECTIONS, snake::Snake},
-            single_gamestate_nodes::situation::{Situation, SituationMatch},
+            single_gamestate_nodes::situation::{Situation, SituationMatch, SituationSet},
         },
         read_game_state,
     };
@@ -663,6 +804,201 @@ mod tests {
         assert_eq!(tree.result()[1], NodeStatus::DeadIn(7));
     }
 
+    #[test]
+    fn option_priority() {
+        // Without time limit the order of exploration does not change the result
+        for priority_fn in [priority::uncertain_root_direction, priority::length_difference] {
+            test_against_base_simulation(
+                |tree| tree.priority(priority_fn),
+                |baseline_tree, tree, filename| {
+                    assert_eq!(
+                        tree.result(),
+                        baseline_tree.result(),
+                        "Root direction statuses should be same as baseline for {}",
+                        filename
+                    );
+                },
+            );
+        }
+
+        // Undecided root directions are explored before decided ones
+        let mut tree = create_tree_from_gamestate("requests/failure_1.json")
+            .all_root_directions()
+            .max_depth(3);
+        tree.simulate();
+        let root = tree.nodes.get(&NodeId::new()).unwrap();
+        let child = |direction| {
+            let id = NodeId::new().child([Some(direction), None, None, None]);
+            Node::new(id, root.gamestate().clone())
+        };
+        assert_eq!(tree.result()[0], NodeStatus::DeadIn(0));
+        assert!(
+            priority::uncertain_root_direction(&tree, &child(Direction::Up), root.gamestate())
+                < priority::uncertain_root_direction(
+                    &tree,
+                    &child(Direction::Down),
+                    root.gamestate()
+                )
+        );
+
+        // Nodes queued again to seek kills are packed by then, the priority still gets their gamestate
+        let filename = "requests/failure_46_go_for_kill.json";
+        let root = GameState::<BasicField>::from(&read_game_state(filename));
+        let mut tree = Tree::compact(root, Storage::Packed)
+            .kill_seeking(3)
+            .max_depth(4)
+            .priority(|tree, node, gamestate| {
+                assert_eq!(node.unpacked().unwrap().to_string(), gamestate.to_string());
+                priority::length_difference(tree, node, gamestate)
+            });
+        tree.simulate();
+        let mut baseline_tree = create_tree_from_gamestate(filename)
+            .kill_seeking(3)
+            .max_depth(4);
+        baseline_tree.simulate();
+        assert_eq!(tree.result(), baseline_tree.result());
+    }
+
+    #[test]
+    fn stops_when_move_no_longer_awaited() {
+        let mut tree = create_tree_from_gamestate("requests/failure_1.json")
+            .max_time(Duration::from_secs(60));
+        let stop = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+        watchdog::with_stop_flag(stop, || tree.simulate());
+        assert!(tree.elapsed < Duration::from_secs(5));
+        assert_eq!(tree.nodes.len(), 1);
+        assert!(!watchdog::stop_requested());
+    }
+
+    #[test]
+    fn option_storage() {
+        let compact = |filename, storage, max_depth| {
+            let root = GameState::<BasicField>::from(&read_game_state(filename));
+            let mut tree = Tree::compact(root, storage).max_depth(max_depth);
+            tree.simulate();
+            tree
+        };
+        for filename in [
+            "requests/failure_1.json",
+            "requests/failure_2.json",
+            "requests/failure_3.json",
+            "requests/failure_4.json",
+            "requests/failure_5.json",
+        ] {
+            let mut baseline_tree = create_tree_from_gamestate(filename).max_depth(4);
+            baseline_tree.simulate();
+            for storage in [Storage::Packed, Storage::Recompute] {
+                let tree = compact(filename, storage, 4);
+                assert_eq!(tree.result(), baseline_tree.result(), "{:?} {}", storage, filename);
+                assert_eq!(tree.nodes.len(), baseline_tree.nodes.len());
+                assert!(tree.nodes.memory_bytes() < baseline_tree.nodes.memory_bytes());
+            }
+        }
+
+        let mut full = create_tree_from_gamestate("requests/failure_1.json").max_depth(3);
+        full.simulate();
+        let packed = compact("requests/failure_1.json", Storage::Packed, 3);
+        let recompute = compact("requests/failure_1.json", Storage::Recompute, 3);
+        assert!(recompute.nodes.memory_bytes() < packed.nodes.memory_bytes());
+        // Compactly stored gamestates come back as they were simulated
+        for id in full.nodes.keys() {
+            let gamestate = full.nodes[id].gamestate().to_string();
+            assert_eq!(packed.gamestate(id).unwrap().to_string(), gamestate);
+            assert_eq!(recompute.gamestate(id).unwrap().to_string(), gamestate);
+        }
+
+        // Gamestates recomputed to rescore a node are the ones it is simulated with
+        let mut full = create_tree_from_gamestate("requests/failure_1.json")
+            .max_depth(3)
+            .priority(priority::length_difference);
+        full.simulate();
+        let root = GameState::<BasicField>::from(&read_game_state("requests/failure_1.json"));
+        let mut recompute = Tree::compact(root, Storage::Recompute)
+            .max_depth(3)
+            .priority(priority::length_difference);
+        recompute.simulate();
+        assert_eq!(recompute.result(), full.result());
+        assert_eq!(recompute.nodes.len(), full.nodes.len());
+        assert!(recompute.scored_gamestate.is_none());
+    }
+
+    #[test]
+    fn option_restrict_moves() {
+        let mut tree = create_tree_from_gamestate("requests/failure_1.json")
+            .max_depth(2)
+            .all_root_directions()
+            .restrict_moves(|_, move_matrix| {
+                for id in 1..4 {
+                    if let Some(moves) = *move_matrix.get(id) {
+                        let first = moves.iter().position(|&valid| valid).unwrap();
+                        move_matrix.set(id, Direction::try_from(first).unwrap().into());
+                    }
+                }
+            });
+        tree.simulate();
+        let root = tree.nodes.get(&NodeId::new()).unwrap();
+        for children in root.children().into_iter().flatten() {
+            assert!(
+                children.len() <= 1,
+                "Restricted opponents should only spawn a single child per direction"
+            );
+        }
+    }
+
+    #[test]
+    fn option_situation_restrictions_skip_root() {
+        let situation_set = SituationSet::parse(
+            "situation Opponent Avoids Dead End\navoid - U\npattern\n W N * *\n W . N *\n W B . A\nend\n",
+        )
+        .unwrap();
+        let mut tree = create_tree_from_gamestate("requests/opponent_dead_end.json")
+            .max_depth(1)
+            .all_root_directions()
+            .situation_restrictions(situation_set.clone());
+        tree.simulate();
+        let root = tree.nodes.get(&NodeId::new()).unwrap();
+        assert!(root.children().into_iter().flatten().any(|children| children.len() == 2));
+
+        let mut tree = create_tree_from_gamestate("requests/opponent_dead_end.json")
+            .max_depth(1)
+            .all_root_directions()
+            .restrict_moves(move |node, move_matrix| {
+                situation_set.restrict_opponents(node.gamestate(), move_matrix)
+            });
+        tree.simulate();
+        let root = tree.nodes.get(&NodeId::new()).unwrap();
+        assert!(root.children().into_iter().flatten().all(|children| children.len() <= 1));
+    }
+
+    #[test]
+    fn option_situation_priorities() {
+        let situation_set = SituationSet::parse(
+            "situation Opponent Avoids Dead End\navoid - U\npattern\n W N * *\n W . N *\n W B . A\nend\n",
+        )
+        .unwrap();
+        let mut full = create_tree_from_gamestate("requests/opponent_dead_end_before.json")
+            .max_depth(2)
+            .all_root_directions();
+        full.simulate();
+        let mut tree = create_tree_from_gamestate("requests/opponent_dead_end_before.json")
+            .max_depth(2)
+            .all_root_directions()
+            .situation_priorities(situation_set.clone());
+        tree.simulate();
+
+        // Every reply is kept, the one into the dead end is only simulated last
+        assert_eq!(tree.nodes.len(), full.nodes.len());
+        let dead_end = NodeId::new().child([Some(Direction::Down), Some(Direction::Up), None, None]);
+        let priority_of = |opponent| {
+            let id = dead_end.child([Some(Direction::Down), Some(opponent), None, None]);
+            priority::implausible_replies(&tree, &tree.nodes[&id], &situation_set)
+        };
+        assert!(priority_of(Direction::Up) < 0);
+        assert_eq!(priority_of(Direction::Right), 0);
+        let root_reply = priority::implausible_replies(&tree, &tree.nodes[&dead_end], &situation_set);
+        assert_eq!(root_reply, 0);
+    }
+
     #[test]
     fn display_tree() {
         let situation = Rc::new(
@@ -783,12 +1119,12 @@ mod benchmarks {
     }
 
     #[bench]
-    fn bench_depth_queue_push_pop(b: &mut test::Bencher) {
+    fn bench_frontier_push_pop(b: &mut test::Bencher) {
         let root = NodeId::new();
         // Pre-build a set of ids at varied depths to push/pop each iteration.
         use crate::logic::general::direction::Direction::*;
         let ids: Vec<NodeId> = vec![
-            root,
+            root.clone(),
             root.child([Some(Up), Some(Down), Some(Left), Some(Right)]),
             root.child([Some(Down), Some(Up), Some(Right), Some(Left)]),
             root.child([Some(Left), Some(Right), Some(Up), Some(Down)])
@@ -800,8 +1136,8 @@ mod benchmarks {
         let mut i = 0;
 
         b.iter(|| {
-            let mut q = DepthQueue::new();
-            q.push(black_box(ids[i % ids.len()]));
+            let mut q = Frontier::new();
+            q.push(black_box(ids[i % ids.len()].clone()), 0);
             let _ = black_box(q.pop());
             i += 1;
         });
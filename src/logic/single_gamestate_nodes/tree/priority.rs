@@ -0,0 +1,85 @@
+use crate::logic::{
+    general::{field::BasicField, game_state::GameState, snake::Snake, snakes::SNAKES},
+    single_gamestate_nodes::{
+        node::{Node, NodeState, NodeStatus, node_id::NodeId},
+        situation::SituationSet,
+    },
+};
+
+use super::Tree;
+
+/// Priority of nodes whose root direction is already decided
+const DECIDED: i32 = -1000;
+/// Priority of each opponent reply a situation rules out, after undecided directions but still
+/// before decided ones
+const IMPLAUSIBLE_REPLY: i32 = -100;
+
+/// Explores breadth first, like a tree without a priority function
+pub fn breadth_first<S: NodeState>(
+    _tree: &Tree<S>,
+    _node: &Node<S>,
+    _gamestate: &GameState<BasicField>,
+) -> i32 {
+    0
+}
+
+/// Prefers nodes below the root direction with the least proven survival, so the budget goes to
+/// directions that are still undecided. Directions that are dead or pruned come last.
+pub fn uncertain_root_direction<S: NodeState>(
+    tree: &Tree<S>,
+    node: &Node<S>,
+    _gamestate: &GameState<BasicField>,
+) -> i32 {
+    let Some(Some(direction)) = node.id().direction_at(0, 0) else {
+        return 0;
+    };
+    let Some(root) = tree.nodes.get(&NodeId::new()) else {
+        return 0;
+    };
+    match root.direction_status(direction) {
+        NodeStatus::AliveFor(n) => -(n as i32),
+        NodeStatus::NotSimulated => 0,
+        _ => DECIDED,
+    }
+}
+
+/// Prefers nodes where we are longer than the longest opponent
+pub fn length_difference<S: NodeState>(
+    _tree: &Tree<S>,
+    _node: &Node<S>,
+    gamestate: &GameState<BasicField>,
+) -> i32 {
+    let lengths = gamestate.snakes().lengths();
+    let longest_opponent = (1..lengths.len())
+        .filter(|&id| matches!(gamestate.snakes().cell(id as u8).get(), Snake::Alive { .. }))
+        .map(|id| lengths[id] as i32)
+        .max()
+        .unwrap_or(0);
+    lengths[0] as i32 - longest_opponent
+}
+
+/// Lowers the priority of a node for each opponent reply leading to it that `situation_set`
+/// rules out in the parent, see `Tree::situation_priorities`. Replies at the root stay as they are.
+pub(super) fn implausible_replies<S: NodeState>(
+    tree: &Tree<S>,
+    node: &Node<S>,
+    situation_set: &SituationSet,
+) -> i32 {
+    let Some(parent_id) = node.id().parent().filter(|parent_id| parent_id.depth() > 0) else {
+        return 0;
+    };
+    let Some(parent) = tree.gamestate(&parent_id) else {
+        return 0;
+    };
+    let mut move_matrix = parent.valid_moves();
+    situation_set.restrict_opponents(&parent, &mut move_matrix);
+    let implausible = (1..SNAKES)
+        .filter(|&id| {
+            matches!(
+                node.id().last_direction_for(id as u8),
+                Some(Some(direction)) if !move_matrix.get(id).is_valid(direction)
+            )
+        })
+        .count();
+    implausible as i32 * IMPLAUSIBLE_REPLY
+}
@@ -9,9 +9,10 @@ use tabled::{
 };
 
 use crate::logic::general::direction::DIRECTIONS;
+use crate::logic::legacy::shared::brain::SearchSummary;
 use crate::logic::{
     general::direction::Direction,
-    single_gamestate_nodes::node::{Node, NodeStatus, node_id::NodeId},
+    single_gamestate_nodes::node::{NodeState, NodeStatus, node_id::NodeId},
 };
 
 const ALL_PRUNED_STATUSES: &[NodeStatus] = &[
@@ -25,8 +26,8 @@ use super::Tree;
 #[derive(Debug)]
 pub struct TreeStats {
     pub total_nodes: usize,
-    pub max_depth_reached: u8,
-    pub nodes_per_depth: Vec<(u8, usize)>,
+    pub max_depth_reached: u16,
+    pub nodes_per_depth: Vec<(u16, usize)>,
     pub pruning_per_depth: Vec<PruningDepthStats>,
     pub nodes_by_status: Vec<(NodeStatus, usize)>,
     pub leaf_nodes: usize,
@@ -43,7 +44,7 @@ pub struct TreeStats {
 
 #[derive(Debug)]
 pub struct PruningDepthStats {
-    pub depth: u8,
+    pub depth: u16,
     pub potential: usize,
     pub dir_skip: usize,
     pub dead_break: usize,
@@ -68,26 +69,26 @@ pub struct DirectionStats {
     pub direction: Direction,
     pub status: Option<NodeStatus>,
     pub subtree_size: usize,
-    pub max_depth: u8,
+    pub max_depth: u16,
 }
 
-impl Tree {
+impl<S: NodeState> Tree<S> {
     pub fn stats(&self) -> TreeStats {
         let root_id = NodeId::new();
         let root = &self.nodes[&root_id];
 
         // Group nodes by depth and build parent->children map
-        let mut by_depth: BTreeMap<u8, usize> = BTreeMap::new();
+        let mut by_depth: BTreeMap<u16, usize> = BTreeMap::new();
         let mut children_map: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
-        for &id in self.nodes.keys() {
+        for id in self.nodes.keys() {
             *by_depth.entry(id.depth()).or_default() += 1;
             if let Some(parent_id) = id.parent() {
-                children_map.entry(parent_id).or_default().push(id);
+                children_map.entry(parent_id).or_default().push(id.clone());
             }
         }
 
         let max_depth_reached = by_depth.keys().last().copied().unwrap_or(0);
-        let nodes_per_depth: Vec<(u8, usize)> = by_depth.iter().map(|(&d, &n)| (d, n)).collect();
+        let nodes_per_depth: Vec<(u16, usize)> = by_depth.iter().map(|(&d, &n)| (d, n)).collect();
 
         // Leaf nodes = nodes with no children in the tree
         let leaf_ids: Vec<&NodeId> = self
@@ -98,7 +99,7 @@ impl Tree {
         let leaf_nodes = leaf_ids.len();
 
         // Leaf depth distribution
-        let mut leaf_depths: Vec<u8> = leaf_ids.iter().map(|id| id.depth()).collect();
+        let mut leaf_depths: Vec<u16> = leaf_ids.iter().map(|id| id.depth()).collect();
         leaf_depths.sort();
         let avg_leaf_depth = if leaf_depths.is_empty() {
             0.0
@@ -118,8 +119,8 @@ impl Tree {
 
         // Count nodes by exact status
         let mut status_counts: HashMap<NodeStatus, usize> = HashMap::new();
-        for node in self.nodes.values() {
-            *status_counts.entry(node.status()).or_default() += 1;
+        for id in self.nodes.keys() {
+            *status_counts.entry(self.nodes[id].status()).or_default() += 1;
         }
         let mut nodes_by_status: Vec<(NodeStatus, usize)> = status_counts.into_iter().collect();
         nodes_by_status.sort_by(
@@ -138,18 +139,20 @@ impl Tree {
         //   dead_break = B - Tree  (explored-direction combos cut short by DeadIn(0))
         //   pruned     = nodes with a pruned status at this depth (dynamic, see NodeStatus::is_pruned)
         //   simulated  = Tree - sum(pruned)
-        let mut potential_all_by_depth: BTreeMap<u8, usize> = BTreeMap::new();
-        let mut potential_eval_by_depth: BTreeMap<u8, usize> = BTreeMap::new();
-        let mut pruned_by_depth: BTreeMap<u8, HashMap<String, (NodeStatus, usize)>> =
+        let mut potential_all_by_depth: BTreeMap<u16, usize> = BTreeMap::new();
+        let mut potential_eval_by_depth: BTreeMap<u16, usize> = BTreeMap::new();
+        let mut pruned_by_depth: BTreeMap<u16, HashMap<String, (NodeStatus, usize)>> =
             BTreeMap::new();
         // virtual_pruned_by_depth: children recorded in parent's children array with a pruned
         // status but never inserted into self.nodes (e.g. PrunedForSimilarity)
-        let mut virtual_pruned_by_depth: BTreeMap<u8, usize> = BTreeMap::new();
-        for (&id, node) in &self.nodes {
+        let mut virtual_pruned_by_depth: BTreeMap<u16, usize> = BTreeMap::new();
+        for id in self.nodes.keys() {
+            let node = &self.nodes[id];
             let child_depth = id.depth() + 1;
-            let a: usize = node.count_potential_children_all().iter().sum();
+            let valid_moves = self.gamestate(id).unwrap().valid_moves();
+            let a: usize = node.count_potential_children_all(&valid_moves).iter().sum();
             let b: usize = node
-                .count_potential_children_from_evaluated_directions()
+                .count_potential_children_from_evaluated_directions(&valid_moves)
                 .iter()
                 .sum();
             *potential_all_by_depth.entry(child_depth).or_default() += a;
@@ -169,7 +172,7 @@ impl Tree {
                 if let Some(children_vec) = direction_slot {
                     for (child_id, child_status) in children_vec {
                         if ALL_PRUNED_STATUSES.contains(&child_status)
-                            && !self.nodes.contains_key(&child_id)
+                            && self.nodes.get(&child_id).is_none()
                         {
                             pruned_by_depth
                                 .entry(child_id.depth())
@@ -211,16 +214,15 @@ impl Tree {
             })
             .collect();
 
-        // Memory estimate (node data + HashMap overhead ~48 bytes/entry)
-        let memory_estimate_bytes =
-            self.nodes.len() * (std::mem::size_of::<NodeId>() + std::mem::size_of::<Node>() + 48);
+        let memory_estimate_bytes = self.nodes.memory_bytes();
 
         // Alive leaves = leaf nodes that are alive
         let alive_leaves = self
             .nodes
-            .iter()
-            .filter(|(id, n)| {
-                !children_map.contains_key(id) && matches!(n.status(), NodeStatus::AliveFor(_))
+            .keys()
+            .filter(|id| {
+                !children_map.contains_key(id)
+                    && matches!(self.nodes[id].status(), NodeStatus::AliveFor(_))
             })
             .count();
 
@@ -273,10 +275,21 @@ impl Tree {
         }
     }
 
-    fn subtree_stats_for_direction(&self, direction: Direction) -> (usize, u8) {
+    /// Node count, depth and memory of `stats` without the per node breakdowns, cheap enough for
+    /// every move
+    pub fn summary(&self) -> SearchSummary {
+        SearchSummary {
+            total_nodes: self.nodes.len(),
+            max_depth_reached: self.nodes.keys().map(|id| id.depth()).max().unwrap_or(0),
+            memory_bytes: self.nodes.memory_bytes(),
+            duration: self.elapsed,
+        }
+    }
+
+    fn subtree_stats_for_direction(&self, direction: Direction) -> (usize, u16) {
         let mut count = 0usize;
-        let mut max_depth = 0u8;
-        for (&id, _) in &self.nodes {
+        let mut max_depth = 0u16;
+        for id in self.nodes.keys() {
             if id.depth() > 0 {
                 if let Some(Some(dir)) = id.direction_at(0, 0) {
                     if dir == direction {
@@ -489,7 +502,7 @@ mod tests {
     };
 
     fn check_invariants(stats: &super::TreeStats, label: &str, filename: &str) {
-        let nodes_per_depth: std::collections::HashMap<u8, usize> =
+        let nodes_per_depth: std::collections::HashMap<u16, usize> =
             stats.nodes_per_depth.iter().copied().collect();
 
         for p in &stats.pruning_per_depth {
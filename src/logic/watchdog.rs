@@ -0,0 +1,256 @@
+use super::{
+    config::{self, Params},
+    general::{
+        coord::Coord,
+        direction::{DIRECTIONS, Direction},
+        evaluation::Evaluation,
+        field::{BasicField, FloodFillField},
+        game_state::GameState,
+    },
+    decide,
+    legacy::shared::brain::Decision,
+};
+use crate::{OriginalCoord, OriginalDirection, OriginalGame, OriginalGameState, RequestError};
+use std::{
+    cell::RefCell,
+    fmt::{Display, Formatter, Result as FmtResult},
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    thread,
+    time::Duration,
+};
+
+/// Time kept free for the network round trip
+const LATENCY_BUFFER_MS: u64 = 100;
+
+thread_local! {
+    static STOP: RefCell<Option<Arc<AtomicBool>>> = const { RefCell::new(None) };
+}
+
+/// Whether the search on this thread should stop, its move is no longer awaited. Searches poll
+/// it next to their own time budget.
+pub fn stop_requested() -> bool {
+    STOP.with(|stop| {
+        stop.borrow()
+            .as_ref()
+            .is_some_and(|stop| stop.load(Ordering::Relaxed))
+    })
+}
+
+/// Runs `f` with `stop` visible to `stop_requested` on this thread
+pub fn with_stop_flag<R>(stop: Arc<AtomicBool>, f: impl FnOnce() -> R) -> R {
+    let previous = STOP.with(|current| current.replace(Some(stop)));
+    let result = f();
+    STOP.with(|current| current.replace(previous));
+    result
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Incident {
+    Panicked(String),
+    TimedOut(Duration),
+    InvalidRequest(RequestError),
+}
+
+impl Display for Incident {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Incident::Panicked(message) => write!(f, "brain panicked: {}", message),
+            Incident::TimedOut(deadline) => {
+                write!(f, "brain exceeded deadline of {}ms", deadline.as_millis())
+            }
+            Incident::InvalidRequest(error) => write!(f, "invalid request: {}", error),
+        }
+    }
+}
+
+/// Hard deadline for the brain, at least half of the game timeout is granted
+pub fn deadline(game: &OriginalGame) -> Duration {
+    let timeout = game.timeout as u64;
+    Duration::from_millis(timeout.saturating_sub(LATENCY_BUFFER_MS).max(timeout / 2))
+}
+
+/// Runs the brain on its own thread with the snake's parameters and answers with the fallback
+/// move if it panics or does not answer before the deadline. An overrunning brain is told to
+/// stop through `stop_requested` and ends its search in the background.
+pub fn get_move_guarded(
+    gamestate: Arc<OriginalGameState>,
+    variant: String,
+    params: Params,
+    deadline: Duration,
+) -> (Decision, Option<Incident>) {
+    let (sender, receiver) = mpsc::channel();
+    let state = gamestate.clone();
+    let stop = Arc::new(AtomicBool::new(false));
+    let brain_stop = stop.clone();
+    let spawned = thread::Builder::new()
+        .name("brain".to_string())
+        .spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                with_stop_flag(brain_stop, || {
+                    config::with_params(params, || decide(&state, variant))
+                })
+            }));
+            let _ = sender.send(result.map_err(|payload| {
+                payload
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown".to_string())
+            }));
+        });
+
+    let incident = match spawned {
+        Err(error) => Incident::Panicked(error.to_string()),
+        Ok(_) => match receiver.recv_timeout(deadline) {
+            Ok(Ok(decision)) => return (decision, None),
+            Ok(Err(message)) => Incident::Panicked(message),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                stop.store(true, Ordering::Relaxed);
+                Incident::TimedOut(deadline)
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                Incident::Panicked("brain thread ended without a move".to_string())
+            }
+        },
+    };
+    // The fallback must not fail the request either, up is what the game engine would pick
+    let decision = panic::catch_unwind(|| fallback_decision(&gamestate))
+        .unwrap_or(Decision::new(OriginalDirection::Up));
+    (decision, Some(incident))
+}
+
+pub fn fallback_move(gamestate: &OriginalGameState) -> OriginalDirection {
+    fallback_decision(gamestate).direction
+}
+
+/// Move for a request the rule engines cannot take, see `validate_game_state`. Reads only the
+/// request: stays on the board and off the bodies, preferring the cell with most free neighbours.
+pub fn request_fallback_decision(gamestate: &OriginalGameState) -> Decision {
+    let board = &gamestate.board;
+    let blocked = |coord: &OriginalCoord| {
+        !(0..board.width).contains(&coord.x)
+            || !(0..board.height as i32).contains(&coord.y)
+            || board.snakes.iter().any(|snake| {
+                // Tails move on, a stacked tail stays in the body before it
+                snake.body[..snake.body.len().saturating_sub(1)].contains(coord)
+            })
+    };
+    let step = |coord: OriginalCoord, direction: Direction| {
+        let offset = Coord::from(direction);
+        OriginalCoord {
+            x: coord.x + offset.x as i32,
+            y: coord.y + offset.y as i32,
+        }
+    };
+    let head = gamestate.you.head;
+    let direction = DIRECTIONS
+        .into_iter()
+        .filter(|&direction| !blocked(&step(head, direction)))
+        .max_by_key(|&direction| {
+            let next = step(head, direction);
+            DIRECTIONS
+                .iter()
+                .filter(|&&onward| !blocked(&step(next, onward)))
+                .count()
+        })
+        .unwrap_or(Direction::Up);
+    Decision::new(direction.into())
+}
+
+/// Cheap evaluation without any simulation: valid moves first, then the largest flooded area
+fn fallback_decision(gamestate: &OriginalGameState) -> Decision {
+    let gamestate: GameState<BasicField> = gamestate.into();
+    let valid_moves = gamestate.valid_moves().get(0);
+    let mut evaluation = Evaluation::new();
+    evaluation.new_section("Fallback");
+    for direction in DIRECTIONS {
+        if !valid_moves.is_valid(direction) {
+            evaluation.eliminate(direction, 0);
+            continue;
+        }
+        let mut state: GameState<FloodFillField> = gamestate.clone().into();
+        let result = state.flood_fill(direction);
+        if let Some(turn) = result.not_enough_area_in_turn[0] {
+            evaluation.eliminate(direction, turn.min(16));
+        }
+        evaluation.score(direction, result.flooded_area[0] as i32, "Flooded Area");
+    }
+    Decision {
+        direction: evaluation.result().into(),
+        reason: Some(evaluation.reason()),
+        search: None,
+        evaluation: Some(evaluation.report()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_game_state;
+
+    #[test]
+    fn test_deadline() {
+        let mut gamestate = read_game_state("requests/failure_1.json");
+        gamestate.game.timeout = 500;
+        assert_eq!(deadline(&gamestate.game), Duration::from_millis(400));
+        gamestate.game.timeout = 150;
+        assert_eq!(deadline(&gamestate.game), Duration::from_millis(75));
+    }
+
+    #[test]
+    fn test_fallback_move() {
+        let gamestate = read_game_state("requests/failure_1.json");
+        assert_ne!(fallback_move(&gamestate), OriginalDirection::Up);
+    }
+
+    #[test]
+    fn test_request_fallback() {
+        let gamestate = read_game_state("requests/failure_1.json");
+        let state = GameState::<BasicField>::from(&gamestate);
+        let valid_moves = state.valid_moves().get(0);
+        let direction = request_fallback_decision(&gamestate).direction;
+        assert!(valid_moves.is_valid(direction.into()));
+
+        // A royale with more snakes than the engines take still gets a move on the board
+        let mut royale = read_game_state("requests/failure_1.json");
+        for i in 0..4 {
+            let mut snake = royale.board.snakes[1].clone();
+            snake.id = format!("extra-{}", i);
+            snake.body.clear();
+            royale.board.snakes.push(snake);
+        }
+        assert!(crate::validate_game_state(&royale).is_err());
+        assert_eq!(request_fallback_decision(&royale).direction, direction);
+    }
+
+    #[test]
+    fn test_panicking_brain() {
+        let gamestate = Arc::new(read_game_state("requests/failure_1.json"));
+        let (decision, incident) = get_move_guarded(
+            gamestate.clone(),
+            "unknown_variant".to_string(),
+            Params::new(),
+            Duration::from_secs(5),
+        );
+        assert_eq!(decision.direction, fallback_move(&gamestate));
+        assert!(matches!(incident, Some(Incident::Panicked(_))));
+    }
+
+    #[test]
+    fn test_overrunning_brain() {
+        let gamestate = Arc::new(read_game_state("requests/failure_1.json"));
+        let (decision, incident) = get_move_guarded(
+            gamestate.clone(),
+            "single_gamestate_nodes".to_string(),
+            Params::new(),
+            Duration::ZERO,
+        );
+        assert_eq!(decision.direction, fallback_move(&gamestate));
+        assert_eq!(incident, Some(Incident::TimedOut(Duration::ZERO)));
+    }
+}
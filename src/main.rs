@@ -1,23 +1,67 @@
 #[macro_use]
 extern crate rocket;
 
-use battlesnake_game_of_chicken_lib::{logic, OriginalGameState};
+use battlesnake_game_of_chicken_lib::{logic, validate_game_state, OriginalGameState};
+use battlesnake_game_of_chicken_lib::logic::config::{ServerConfig, SnakeConfig};
 use battlesnake_game_of_chicken_lib::logic::general::field::BasicField;
 use battlesnake_game_of_chicken_lib::logic::general::game_state::GameState;
-use log::{info, warn};
+use battlesnake_game_of_chicken_lib::logic::opponents::{self, OpponentProfiles};
+use battlesnake_game_of_chicken_lib::logic::watchdog;
+use battlesnake_game_of_chicken_lib::logic::single_gamestate_nodes::GamestateNodesSnake;
+use battlesnake_game_of_chicken_lib::metrics::Metrics;
+use battlesnake_game_of_chicken_lib::turn_log::TurnLog;
+use log::{error, info, warn};
 use rocket::fairing::AdHoc;
 use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
 use rocket::serde::json::Json;
+use rocket::State;
 use serde_json::{json, Value};
 use std::env;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// The configured snake whose routes matched the request
+struct Snake<'r>(&'r SnakeConfig);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Snake<'r> {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, ()> {
+        let snake = req
+            .rocket()
+            .state::<ServerConfig>()
+            .zip(req.route())
+            .and_then(|(config, route)| config.snake(route.uri.base()));
+        match snake {
+            Some(snake) => Outcome::Success(Snake(snake)),
+            None => Outcome::Forward(Status::NotFound),
+        }
+    }
+}
 
 #[get("/")]
-fn handle_index() -> Json<Value> {
-    Json(logic::info())
+fn handle_index(snake: Snake, metrics: &State<Metrics>) -> Json<Value> {
+    metrics.request(&snake.0.base(), "index");
+    info!("INFO {}", snake.0.base());
+    Json(snake.0.info())
 }
 
 #[post("/start", format = "json", data = "<start_req>")]
-fn handle_start(start_req: Json<OriginalGameState>) -> Status {
+fn handle_start(
+    snake: Snake,
+    metrics: &State<Metrics>,
+    start_req: Json<OriginalGameState>,
+) -> Status {
+    metrics.request(&snake.0.base(), "start");
+    metrics.game_started(&snake.0.base());
+    info!(
+        "ID {} Snake {} Variant {}",
+        start_req.game.id,
+        snake.0.base(),
+        snake.0.variant
+    );
     logic::start(
         &start_req.game,
         &start_req.turn,
@@ -29,7 +73,13 @@ fn handle_start(start_req: Json<OriginalGameState>) -> Status {
 }
 
 #[post("/move", format = "json", data = "<move_req>")]
-fn handle_move(mut move_req: Json<OriginalGameState>) -> Json<Value> {
+fn handle_move(
+    snake: Snake,
+    metrics: &State<Metrics>,
+    move_req: Json<OriginalGameState>,
+) -> Json<Value> {
+    let start = Instant::now();
+    metrics.request(&snake.0.base(), "move");
     // Log request
     let r = move_req.into_inner();
     warn!(
@@ -39,33 +89,109 @@ fn handle_move(mut move_req: Json<OriginalGameState>) -> Json<Value> {
         serde_json::to_string(&r).unwrap()
     );
 
-    let board_state = GameState::<BasicField>::from(&r);
-    if env::var("LOG_BOARD").is_ok() {
-        warn!("ID {} Turn {} Board\n{}", r.game.id, r.turn, board_state);
+    // Panics and overruns of the brain are answered with a fallback move, requests the rule
+    // engines cannot take with a move read from the request alone
+    let deadline = watchdog::deadline(&r.game);
+    let gamestate = Arc::new(r);
+    let (decision, incident) = match validate_game_state(&gamestate) {
+        Err(error) => (
+            watchdog::request_fallback_decision(&gamestate),
+            Some(watchdog::Incident::InvalidRequest(error)),
+        ),
+        Ok(()) => {
+            // Opponent moves since the last turn are profiled before the brain reads the stances
+            opponents::profiles().observe(&gamestate);
+
+            if env::var("LOG_BOARD").is_ok() {
+                let board_state = GameState::<BasicField>::from(gamestate.as_ref());
+                warn!(
+                    "ID {} Turn {} Board\n{}",
+                    gamestate.game.id, gamestate.turn, board_state
+                );
+            }
+
+            watchdog::get_move_guarded(
+                gamestate.clone(),
+                snake.0.variant.clone(),
+                snake.0.params.clone(),
+                deadline,
+            )
+        }
+    };
+    let response = decision.direction;
+    if let Some(incident) = &incident {
+        error!(
+            "ID {} Turn {} Fallback -> {} after {}",
+            gamestate.game.id, gamestate.turn, response, incident
+        );
     }
 
-    let variant = env::var("VARIANT").unwrap_or(String::from("breadth_first"));
-
-    move_req = Json(r);
-    let response = logic::get_move(&move_req, variant);
-
     warn!(
         "ID {} Turn {} Result -> {}",
-        move_req.game.id,
-        move_req.turn,
+        gamestate.game.id,
+        gamestate.turn,
         response
     );
 
-    Json(json!({ "move": response }))
+    metrics.turn(
+        &snake.0.base(),
+        &decision,
+        incident.as_ref(),
+        start.elapsed(),
+    );
+
+    // One json object per line on stdout, the other logs go to stderr
+    if snake.0.param("LOG_JSON").is_some() {
+        let log = TurnLog::new(
+            &gamestate,
+            &snake.0.base(),
+            &snake.0.variant,
+            &decision,
+            incident.as_ref(),
+            deadline,
+            start.elapsed(),
+        );
+        println!("{}", log.to_json());
+    }
+
+    // Spectators see the shout in the replay, it explains the move from the evaluation
+    let shout = decision
+        .evaluation
+        .as_ref()
+        .filter(|_| snake.0.param("SHOUT").is_some())
+        .and_then(|evaluation| evaluation.shout());
+    match shout {
+        Some(shout) => Json(json!({ "move": response, "shout": shout })),
+        None => Json(json!({ "move": response })),
+    }
 }
 
 #[post("/end", format = "json", data = "<end_req>")]
-fn handle_end(end_req: Json<OriginalGameState>) -> Status {
+fn handle_end(snake: Snake, metrics: &State<Metrics>, end_req: Json<OriginalGameState>) -> Status {
+    metrics.request(&snake.0.base(), "end");
+    let snakes = &end_req.board.snakes;
+    let won = snakes.len() == 1 && snakes[0].id == end_req.you.id;
+    metrics.game_ended(&snake.0.base(), won);
+    // The database is written while holding the profiles, so concurrent games cannot interleave
+    {
+        let mut profiles = opponents::profiles();
+        profiles.end_game(&end_req);
+        if let Ok(path) = env::var("OPPONENTS_DB")
+            && let Err(e) = profiles.save(&path)
+        {
+            error!("{}", e);
+        }
+    }
     logic::end(&end_req.game, &end_req.turn, &end_req.board, &end_req.you);
 
     Status::Ok
 }
 
+#[get("/metrics")]
+fn handle_metrics(metrics: &State<Metrics>) -> String {
+    metrics.render()
+}
+
 #[launch]
 fn rocket() -> _ {
     // Lots of web hosting services expect you to bind to the port specified by the `PORT`
@@ -89,14 +215,33 @@ fn rocket() -> _ {
 
     info!("Starting Battlesnake Server...");
 
-    rocket::build()
+    // Opponents known from earlier runs are modelled from the first move
+    if let Ok(path) = env::var("OPPONENTS_DB") {
+        let loaded = OpponentProfiles::load(&path).unwrap_or_else(|e| panic!("{}", e));
+        opponents::profiles().replace_by_name(loaded);
+    }
+
+    // Every configured snake gets its own set of routes below its path
+    let config = ServerConfig::from_env().unwrap_or_else(|e| panic!("{}", e));
+    let mut rocket = rocket::build();
+    for snake in &config.snakes {
+        if let Some(path) = snake.param("SITUATIONS_FILE") {
+            GamestateNodesSnake::check_situation_file(&path).unwrap_or_else(|e| panic!("{}", e));
+        }
+        info!("Serving {} at {}", snake.variant, snake.base());
+        rocket = rocket.mount(
+            snake.base(),
+            routes![handle_index, handle_start, handle_move, handle_end],
+        );
+    }
+
+    rocket
+        .mount("/", routes![handle_metrics])
+        .manage(config)
+        .manage(Metrics::new())
         .attach(AdHoc::on_response("Server ID Middleware", |_, res| {
             Box::pin(async move {
                 res.set_raw_header("Server", "battlesnake/github/starter-snake-rust");
             })
         }))
-        .mount(
-            "/",
-            routes![handle_index, handle_start, handle_move, handle_end],
-        )
 }
@@ -0,0 +1,305 @@
+use crate::logic::{legacy::shared::brain::Decision, watchdog::Incident};
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+const LATENCY_BUCKETS: &[f64] = &[0.01, 0.025, 0.05, 0.1, 0.2, 0.3, 0.4, 0.5, 1.0];
+const NODE_BUCKETS: &[f64] = &[1e2, 1e3, 1e4, 1e5, 1e6];
+const DEPTH_BUCKETS: &[f64] = &[2.0, 4.0, 6.0, 8.0, 12.0, 16.0, 20.0, 24.0, 28.0];
+const MEMORY_BUCKETS: &[f64] = &[1e5, 1e6, 1e7, 1e8, 1e9];
+
+/// Counters per label set, the labels are kept rendered, e.g. `snake="/",route="move"`
+#[derive(Default)]
+struct Counter(BTreeMap<String, u64>);
+
+impl Counter {
+    fn inc(&mut self, labels: String) {
+        *self.0.entry(labels).or_default() += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} counter", name);
+        for (labels, value) in &self.0 {
+            let _ = writeln!(out, "{}{{{}}} {}", name, labels, value);
+        }
+    }
+}
+
+#[derive(Default, Clone)]
+struct HistogramSeries {
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+struct Histogram {
+    bounds: &'static [f64],
+    series: BTreeMap<String, HistogramSeries>,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            series: BTreeMap::new(),
+        }
+    }
+
+    fn observe(&mut self, labels: String, value: f64) {
+        let series = self.series.entry(labels).or_insert_with(|| HistogramSeries {
+            buckets: vec![0; self.bounds.len()],
+            ..HistogramSeries::default()
+        });
+        for (bucket, bound) in series.buckets.iter_mut().zip(self.bounds) {
+            if value <= *bound {
+                *bucket += 1;
+            }
+        }
+        series.sum += value;
+        series.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        for (labels, series) in &self.series {
+            for (bucket, bound) in series.buckets.iter().zip(self.bounds) {
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{{{},le=\"{}\"}} {}",
+                    name, labels, bound, bucket
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{},le=\"+Inf\"}} {}",
+                name, labels, series.count
+            );
+            let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, series.sum);
+            let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, series.count);
+        }
+    }
+}
+
+struct State {
+    requests: Counter,
+    games_started: Counter,
+    games_ended: Counter,
+    games_won: Counter,
+    fallbacks: Counter,
+    decisions: Counter,
+    move_latency: Histogram,
+    search_nodes: Histogram,
+    search_depth: Histogram,
+    search_memory: Histogram,
+}
+
+/// Server metrics in the Prometheus text format, labeled by the path of the snake
+pub struct Metrics {
+    state: Mutex<State>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(State {
+                requests: Counter::default(),
+                games_started: Counter::default(),
+                games_ended: Counter::default(),
+                games_won: Counter::default(),
+                fallbacks: Counter::default(),
+                decisions: Counter::default(),
+                move_latency: Histogram::new(LATENCY_BUCKETS),
+                search_nodes: Histogram::new(NODE_BUCKETS),
+                search_depth: Histogram::new(DEPTH_BUCKETS),
+                search_memory: Histogram::new(MEMORY_BUCKETS),
+            }),
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut State)) {
+        // A panic while holding the lock leaves the counters intact, keep counting
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        f(&mut state);
+    }
+
+    pub fn request(&self, snake: &str, route: &str) {
+        self.update(|state| {
+            state
+                .requests
+                .inc(format!("snake=\"{}\",route=\"{}\"", snake, route))
+        });
+    }
+
+    pub fn game_started(&self, snake: &str) {
+        self.update(|state| state.games_started.inc(snake_label(snake)));
+    }
+
+    pub fn game_ended(&self, snake: &str, won: bool) {
+        self.update(|state| {
+            state.games_ended.inc(snake_label(snake));
+            if won {
+                state.games_won.inc(snake_label(snake));
+            }
+        });
+    }
+
+    pub fn turn(
+        &self,
+        snake: &str,
+        decision: &Decision,
+        incident: Option<&Incident>,
+        latency: Duration,
+    ) {
+        self.update(|state| {
+            state
+                .move_latency
+                .observe(snake_label(snake), latency.as_secs_f64());
+            if let Some(incident) = incident {
+                let kind = match incident {
+                    Incident::Panicked(_) => "panicked",
+                    Incident::TimedOut(_) => "timed_out",
+                    Incident::InvalidRequest(_) => "invalid_request",
+                };
+                state
+                    .fallbacks
+                    .inc(format!("snake=\"{}\",incident=\"{}\"", snake, kind));
+            }
+            if let Some(reason) = decision.reason {
+                state
+                    .decisions
+                    .inc(format!("snake=\"{}\",reason=\"{}\"", snake, reason));
+            }
+            if let Some(search) = &decision.search {
+                state
+                    .search_nodes
+                    .observe(snake_label(snake), search.total_nodes as f64);
+                state
+                    .search_depth
+                    .observe(snake_label(snake), search.max_depth_reached as f64);
+                state
+                    .search_memory
+                    .observe(snake_label(snake), search.memory_bytes as f64);
+            }
+        });
+    }
+
+    pub fn render(&self) -> String {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut out = String::new();
+        state.requests.render(
+            &mut out,
+            "battlesnake_requests_total",
+            "Requests per snake and route",
+        );
+        state.games_started.render(
+            &mut out,
+            "battlesnake_games_started_total",
+            "Games started",
+        );
+        state
+            .games_ended
+            .render(&mut out, "battlesnake_games_ended_total", "Games ended");
+        state.games_won.render(
+            &mut out,
+            "battlesnake_games_won_total",
+            "Games ended with our snake as the last one alive",
+        );
+        state.fallbacks.render(
+            &mut out,
+            "battlesnake_fallback_moves_total",
+            "Moves answered by the fallback after the brain panicked or timed out",
+        );
+        state.decisions.render(
+            &mut out,
+            "battlesnake_decisions_total",
+            "Moves decided by elimination, by score or by default",
+        );
+        state.move_latency.render(
+            &mut out,
+            "battlesnake_move_latency_seconds",
+            "Time spent answering a move request",
+        );
+        state.search_nodes.render(
+            &mut out,
+            "battlesnake_search_nodes",
+            "Nodes in the search tree of a move",
+        );
+        state.search_depth.render(
+            &mut out,
+            "battlesnake_search_depth",
+            "Deepest level of the search tree of a move",
+        );
+        state.search_memory.render(
+            &mut out,
+            "battlesnake_search_memory_bytes",
+            "Bytes held by the search tree of a move",
+        );
+        out
+    }
+}
+
+fn snake_label(snake: &str) -> String {
+    format!("snake=\"{}\"", snake)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        OriginalDirection,
+        logic::{general::evaluation::Reason, legacy::shared::brain::SearchSummary},
+    };
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::new();
+        metrics.request("/", "move");
+        metrics.request("/", "move");
+        metrics.game_ended("/", true);
+        let decision = Decision {
+            direction: OriginalDirection::Up,
+            reason: Some(Reason::Elimination),
+            search: Some(SearchSummary {
+                total_nodes: 5000,
+                max_depth_reached: 7,
+                memory_bytes: 2_000_000,
+                duration: Duration::from_millis(100),
+            }),
+            evaluation: None,
+        };
+        metrics.turn(
+            "/",
+            &decision,
+            Some(&Incident::TimedOut(Duration::ZERO)),
+            Duration::from_millis(150),
+        );
+
+        let out = metrics.render();
+        assert!(out.contains("battlesnake_requests_total{snake=\"/\",route=\"move\"} 2\n"));
+        assert!(out.contains("battlesnake_games_won_total{snake=\"/\"} 1\n"));
+        assert!(out.contains(
+            "battlesnake_fallback_moves_total{snake=\"/\",incident=\"timed_out\"} 1\n"
+        ));
+        assert!(
+            out.contains("battlesnake_decisions_total{snake=\"/\",reason=\"elimination\"} 1\n")
+        );
+        assert!(out.contains("battlesnake_move_latency_seconds_bucket{snake=\"/\",le=\"0.1\"} 0\n"));
+        assert!(out.contains("battlesnake_move_latency_seconds_bucket{snake=\"/\",le=\"0.2\"} 1\n"));
+        assert!(out.contains("battlesnake_search_nodes_bucket{snake=\"/\",le=\"10000\"} 1\n"));
+        assert!(out.contains("battlesnake_search_depth_count{snake=\"/\"} 1\n"));
+        assert!(out.contains(
+            "battlesnake_search_memory_bytes_bucket{snake=\"/\",le=\"10000000\"} 1\n"
+        ));
+    }
+}
@@ -0,0 +1,496 @@
+use crate::{
+    OriginalDirection,
+    logic::{
+        config::{self, Params},
+        decide,
+        general::direction::{DIRECTIONS, Direction},
+        general::evaluation_report::{EvaluationDiff, EvaluationReport},
+        legacy::shared::brain::Decision,
+    },
+    read_game_state,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+};
+use tabled::{
+    builder::Builder,
+    settings::{Alignment, Style, object::Columns},
+};
+
+pub const MANIFEST: &str = "requests/manifest.json";
+
+const DEFAULT_TIME_MS: u64 = 200;
+/// Time budget of entries with a node budget, only a safeguard as the nodes run out first
+const NODE_BUDGET_TIME_MS: u64 = 60_000;
+const DEFAULT_VARIANT: &str = "single_gamestate_nodes";
+
+/// Evaluations of a run keyed by `TestResult::key`, to explain changed decisions of a later run
+pub type Evaluations = BTreeMap<String, EvaluationReport>;
+
+pub fn load_evaluations(path: &str) -> Result<Evaluations, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Cannot parse {}: {}", path, e))
+}
+
+/// Moves a variant may pick for a state file.
+/// An empty allowed set accepts every move that is not forbidden.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+pub struct Expectation {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed: Vec<OriginalDirection>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forbidden: Vec<OriginalDirection>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skip: bool,
+}
+
+impl Expectation {
+    pub fn check(&self, direction: OriginalDirection) -> bool {
+        (self.allowed.is_empty() || self.allowed.contains(&direction))
+            && !self.forbidden.contains(&direction)
+    }
+
+    /// Directions labelled good and bad: allowed moves are good, forbidden moves and moves
+    /// besides the allowed ones are bad
+    pub fn labels(&self) -> (Vec<Direction>, Vec<Direction>) {
+        let good = self.allowed.iter().map(|&d| d.into()).collect();
+        let bad = DIRECTIONS
+            .into_iter()
+            .filter(|&d| !self.check(d.into()))
+            .collect();
+        (good, bad)
+    }
+}
+
+impl Display for Expectation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let join = |directions: &[OriginalDirection]| {
+            directions
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("|")
+        };
+        match (self.allowed.is_empty(), self.forbidden.is_empty()) {
+            (true, true) => write!(f, "any"),
+            (false, true) => write!(f, "{}", join(&self.allowed)),
+            (true, false) => write!(f, "not {}", join(&self.forbidden)),
+            (false, false) => write!(
+                f,
+                "{} not {}",
+                join(&self.allowed),
+                join(&self.forbidden)
+            ),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ManifestEntry {
+    /// Path of the state file, relative to the manifest
+    pub file: String,
+    #[serde(flatten)]
+    pub expectation: Expectation,
+    /// Overrides the manifest wide time budget
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_ms: Option<u64>,
+    /// Node budget instead of a time budget, for decisions that must not depend on the load of
+    /// the machine
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nodes: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Replaces the default expectation for the named variants
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variants: BTreeMap<String, Expectation>,
+}
+
+impl ManifestEntry {
+    pub fn new(file: impl Into<String>, expectation: Expectation) -> Self {
+        Self {
+            file: file.into(),
+            expectation,
+            time_ms: None,
+            nodes: None,
+            tags: Vec::new(),
+            variants: BTreeMap::new(),
+        }
+    }
+
+    pub fn expectation(&self, variant: &str) -> &Expectation {
+        self.variants.get(variant).unwrap_or(&self.expectation)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Manifest {
+    #[serde(default = "default_time_ms")]
+    pub time_ms: u64,
+    /// Variants that are run when no variant is requested explicitly
+    #[serde(default = "default_variants")]
+    pub variants: Vec<String>,
+    pub tests: Vec<ManifestEntry>,
+}
+
+fn default_time_ms() -> u64 {
+    DEFAULT_TIME_MS
+}
+
+fn default_variants() -> Vec<String> {
+    vec![DEFAULT_VARIANT.to_string()]
+}
+
+impl Manifest {
+    pub fn new() -> Self {
+        Self {
+            time_ms: DEFAULT_TIME_MS,
+            variants: default_variants(),
+            tests: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Cannot parse {}: {}", path, e))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, content + "\n").map_err(|e| format!("Cannot write {}: {}", path, e))
+    }
+
+    /// Builds a manifest from all json files of a directory.
+    /// All trailing direction tokens of a filename are allowed, e.g. `..._safe_up_left.json`.
+    pub fn from_directory(dir: &str) -> Result<Self, String> {
+        let entries = fs::read_dir(dir).map_err(|e| format!("Cannot read {}: {}", dir, e))?;
+        let mut files: Vec<String> = entries
+            .flatten()
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        files.sort();
+        let mut manifest = Self::new();
+        for file in files {
+            let allowed = file
+                .strip_suffix(".json")
+                .unwrap_or(&file)
+                .rsplit('_')
+                .map_while(|token| token.parse().ok())
+                .collect();
+            let expectation = Expectation {
+                allowed,
+                ..Default::default()
+            };
+            manifest.tests.push(ManifestEntry::new(file, expectation));
+        }
+        Ok(manifest)
+    }
+
+    /// Resolves a runner target to the directory of its state files and its manifest:
+    /// a directory with a `manifest.json`, a directory of named state files or a manifest path
+    pub fn resolve(target: &str) -> Result<(String, Self), String> {
+        let path = Path::new(target);
+        if path.is_dir() {
+            let manifest_path = path.join("manifest.json");
+            if manifest_path.exists() {
+                Ok((target.to_string(), Self::load(&manifest_path.to_string_lossy())?))
+            } else {
+                Ok((target.to_string(), Self::from_directory(target)?))
+            }
+        } else {
+            let dir = path
+                .parent()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default();
+            Ok((dir, Self::load(target)?))
+        }
+    }
+
+    /// Runs every entry for every variant and reports each result as soon as it is available.
+    /// State files are resolved relative to `dir`, entries can be narrowed down to a tag.
+    pub fn run(
+        &self,
+        dir: &str,
+        variants: &[String],
+        tag: Option<&str>,
+        mut on_result: impl FnMut(&TestResult),
+    ) -> Report {
+        let variants = if variants.is_empty() {
+            &self.variants
+        } else {
+            variants
+        };
+        let mut results = Vec::new();
+        for variant in variants {
+            for entry in &self.tests {
+                if tag.is_some_and(|tag| !entry.tags.iter().any(|t| t == tag)) {
+                    continue;
+                }
+                let expectation = entry.expectation(variant);
+                if expectation.skip {
+                    continue;
+                }
+                let path = Path::new(dir).join(&entry.file);
+                let budget = match entry.nodes {
+                    Some(nodes) => Budget::Nodes(nodes),
+                    None => Budget::Time(entry.time_ms.unwrap_or(self.time_ms)),
+                };
+                let decision = run_single(&path.to_string_lossy(), variant, budget);
+                let result = TestResult {
+                    file: entry.file.clone(),
+                    variant: variant.clone(),
+                    tags: entry.tags.clone(),
+                    expectation: expectation.clone(),
+                    actual: decision.as_ref().map(|d| d.direction),
+                    evaluation: decision.and_then(|d| d.evaluation),
+                };
+                on_result(&result);
+                results.push(result);
+            }
+        }
+        Report { results }
+    }
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Search budget of a single run
+enum Budget {
+    Time(u64),
+    Nodes(usize),
+}
+
+fn run_single(path: &str, variant: &str, budget: Budget) -> Option<Decision> {
+    // Params are per thread, so runs in parallel keep their own budget
+    let mut params = Params::from([("MODE".to_string(), "test".to_string())]);
+    let time_ms = match budget {
+        Budget::Time(time_ms) => time_ms,
+        Budget::Nodes(nodes) => {
+            params.insert("SIMULATION_NODES".to_string(), nodes.to_string());
+            NODE_BUDGET_TIME_MS
+        }
+    };
+    params.insert("SIMULATION_TIME_MS".to_string(), time_ms.to_string());
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        config::with_params(params, || {
+            let gamestate = read_game_state(path);
+            decide(&gamestate, variant.to_string())
+        })
+    }))
+    .ok()
+}
+
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    pub file: String,
+    pub variant: String,
+    pub tags: Vec<String>,
+    pub expectation: Expectation,
+    /// None if the variant panicked
+    pub actual: Option<OriginalDirection>,
+    /// Only variants that use `Evaluation` report it
+    pub evaluation: Option<EvaluationReport>,
+}
+
+impl TestResult {
+    pub fn key(&self) -> String {
+        format!("{} [{}]", self.file, self.variant)
+    }
+
+    pub fn passed(&self) -> bool {
+        self.actual.is_some_and(|d| self.expectation.check(d))
+    }
+}
+
+impl Display for TestResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self.actual {
+            Some(actual) if self.passed() => write!(f, "ok ({})", actual),
+            Some(actual) => write!(f, "FAIL (expected: {}, got: {})", self.expectation, actual),
+            None => write!(f, "PANICKED"),
+        }
+    }
+}
+
+pub struct Report {
+    pub results: Vec<TestResult>,
+}
+
+impl Report {
+    pub fn failures(&self) -> Vec<&TestResult> {
+        self.results.iter().filter(|r| !r.passed()).collect()
+    }
+
+    pub fn evaluations(&self) -> Evaluations {
+        self.results
+            .iter()
+            .filter_map(|r| Some((r.key(), r.evaluation.clone()?)))
+            .collect()
+    }
+
+    pub fn save_evaluations(&self, path: &str) -> Result<(), String> {
+        let content =
+            serde_json::to_string_pretty(&self.evaluations()).map_err(|e| e.to_string())?;
+        fs::write(path, content + "\n").map_err(|e| format!("Cannot write {}: {}", path, e))
+    }
+
+    /// Results that picked a different move than in `baseline`, with what changed in the evaluation
+    pub fn changed_decisions(&self, baseline: &Evaluations) -> Vec<(&TestResult, EvaluationDiff)> {
+        self.results
+            .iter()
+            .filter_map(|result| {
+                let before = baseline.get(&result.key())?;
+                let after = result.evaluation.as_ref()?;
+                (before.result != after.result).then(|| (result, before.diff(after)))
+            })
+            .collect()
+    }
+
+    /// (passed, total) per variant
+    pub fn by_variant(&self) -> BTreeMap<String, (usize, usize)> {
+        let mut rates = BTreeMap::new();
+        for result in &self.results {
+            Self::count(&mut rates, &result.variant, result.passed());
+        }
+        rates
+    }
+
+    /// (passed, total) per tag, results without tags are counted as `untagged`
+    pub fn by_tag(&self) -> BTreeMap<String, (usize, usize)> {
+        let mut rates = BTreeMap::new();
+        for result in &self.results {
+            if result.tags.is_empty() {
+                Self::count(&mut rates, "untagged", result.passed());
+            }
+            for tag in &result.tags {
+                Self::count(&mut rates, tag, result.passed());
+            }
+        }
+        rates
+    }
+
+    fn count(rates: &mut BTreeMap<String, (usize, usize)>, key: &str, passed: bool) {
+        let rate = rates.entry(key.to_string()).or_insert((0, 0));
+        rate.0 += passed as usize;
+        rate.1 += 1;
+    }
+
+    fn table(title: &str, rates: &BTreeMap<String, (usize, usize)>) -> String {
+        let mut builder = Builder::default();
+        builder.push_record([title, "Passed", "Total", "Rate"]);
+        for (key, (passed, total)) in rates {
+            builder.push_record([
+                key.clone(),
+                passed.to_string(),
+                total.to_string(),
+                format!("{:.1}%", *passed as f64 * 100.0 / *total as f64),
+            ]);
+        }
+        let mut table = builder.build();
+        table
+            .with(Style::rounded())
+            .modify(Columns::new(1..), Alignment::right());
+        table.to_string()
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        writeln!(f, "{}", Self::table("Variant", &self.by_variant()))?;
+        write!(f, "{}", Self::table("Tag", &self.by_tag()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expectation_check() {
+        let expectation = Expectation {
+            allowed: vec![OriginalDirection::Up, OriginalDirection::Left],
+            forbidden: vec![OriginalDirection::Left],
+            skip: false,
+        };
+        assert!(expectation.check(OriginalDirection::Up));
+        assert!(!expectation.check(OriginalDirection::Left));
+        assert!(!expectation.check(OriginalDirection::Down));
+        assert!(Expectation::default().check(OriginalDirection::Right));
+
+        let (good, bad) = expectation.labels();
+        assert_eq!(good, vec![Direction::Up, Direction::Left]);
+        assert!(bad.contains(&Direction::Left) && bad.contains(&Direction::Down));
+        assert!(!bad.contains(&Direction::Up));
+        assert_eq!(Expectation::default().labels(), (vec![], vec![]));
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest: Manifest = serde_json::from_str(
+            r#"{
+                "tests": [
+                    {
+                        "file": "failure_1.json",
+                        "forbidden": ["up"],
+                        "time_ms": 50,
+                        "tags": ["survival"],
+                        "variants": {
+                            "simple_hungry": { "skip": true },
+                            "depth_first": { "allowed": ["down", "left"] }
+                        }
+                    },
+                    { "file": "failure_8.json", "allowed": ["down"], "nodes": 10000 }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.time_ms, DEFAULT_TIME_MS);
+        assert_eq!(manifest.variants, vec![DEFAULT_VARIANT.to_string()]);
+        let entry = &manifest.tests[0];
+        assert_eq!(entry.time_ms, Some(50));
+        assert!(!entry.expectation(DEFAULT_VARIANT).check(OriginalDirection::Up));
+        assert!(entry.expectation("simple_hungry").skip);
+        assert!(!entry.expectation("depth_first").check(OriginalDirection::Right));
+        assert_eq!(entry.nodes, None);
+        assert_eq!(manifest.tests[1].nodes, Some(10000));
+        let roundtrip: Manifest =
+            serde_json::from_str(&serde_json::to_string(&manifest).unwrap()).unwrap();
+        assert_eq!(roundtrip, manifest);
+    }
+
+    #[test]
+    fn test_run_manifest() {
+        let mut manifest = Manifest::new();
+        let mut entry = ManifestEntry::new(
+            "failure_1.json",
+            Expectation {
+                forbidden: vec![OriginalDirection::Up],
+                ..Default::default()
+            },
+        );
+        entry.time_ms = Some(20);
+        entry.tags.push("survival".to_string());
+        manifest.tests.push(entry);
+        manifest
+            .tests
+            .push(ManifestEntry::new("missing.json", Expectation::default()));
+        let report = manifest.run("requests", &[], None, |_| {});
+        assert_eq!(report.results.len(), 2);
+        assert!(report.results[0].passed());
+        assert_eq!(report.results[1].actual, None);
+        assert_eq!(report.by_tag().get("survival"), Some(&(1, 1)));
+        assert_eq!(report.by_variant().get(DEFAULT_VARIANT), Some(&(1, 2)));
+        let report = manifest.run("requests", &[], Some("survival"), |_| {});
+        assert_eq!(report.results.len(), 1);
+    }
+}
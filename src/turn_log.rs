@@ -0,0 +1,122 @@
+use crate::{
+    OriginalDirection, OriginalGameState,
+    logic::{
+        general::{
+            direction::DIRECTIONS,
+            evaluation::{Reason, SectionSummary},
+        },
+        legacy::shared::brain::Decision,
+        watchdog::Incident,
+    },
+};
+use serde::Serialize;
+use std::time::Duration;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SearchLog {
+    pub total_nodes: usize,
+    pub max_depth_reached: u16,
+    pub memory_bytes: usize,
+    pub duration_ms: f64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TimingLog {
+    /// Time spent answering the request, including validation and the watchdog
+    pub total_ms: f64,
+    /// Time granted to the brain before the fallback takes over
+    pub deadline_ms: f64,
+}
+
+/// One line of the structured log, written per move when `LOG_JSON` is set.
+/// Per direction arrays are ordered as `directions`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TurnLog {
+    pub game_id: String,
+    pub turn: i32,
+    pub snake: String,
+    pub variant: String,
+    #[serde(rename = "move")]
+    pub direction: OriginalDirection,
+    pub reason: Option<Reason>,
+    pub fallback: Option<String>,
+    pub directions: [OriginalDirection; 4],
+    pub sections: Vec<SectionSummary>,
+    pub search: Option<SearchLog>,
+    pub timing: TimingLog,
+}
+
+impl TurnLog {
+    pub fn new(
+        gamestate: &OriginalGameState,
+        snake: &str,
+        variant: &str,
+        decision: &Decision,
+        incident: Option<&Incident>,
+        deadline: Duration,
+        total: Duration,
+    ) -> Self {
+        Self {
+            game_id: gamestate.game.id.clone(),
+            turn: gamestate.turn,
+            snake: snake.to_string(),
+            variant: variant.to_string(),
+            direction: decision.direction,
+            reason: decision.reason,
+            fallback: incident.map(ToString::to_string),
+            directions: DIRECTIONS.map(OriginalDirection::from),
+            sections: decision
+                .evaluation
+                .as_ref()
+                .map(|evaluation| evaluation.section_summaries())
+                .unwrap_or_default(),
+            search: decision.search.map(|search| SearchLog {
+                total_nodes: search.total_nodes,
+                max_depth_reached: search.max_depth_reached,
+                memory_bytes: search.memory_bytes,
+                duration_ms: millis(search.duration),
+            }),
+            timing: TimingLog {
+                total_ms: millis(total),
+                deadline_ms: millis(deadline),
+            },
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{logic::watchdog::fallback_move, read_game_state};
+    use serde_json::Value;
+
+    #[test]
+    fn test_turn_log() {
+        let gamestate = read_game_state("requests/failure_1.json");
+        let decision = Decision::new(fallback_move(&gamestate));
+        let log = TurnLog::new(
+            &gamestate,
+            "/",
+            "single_gamestate_nodes",
+            &decision,
+            Some(&Incident::TimedOut(Duration::from_millis(400))),
+            Duration::from_millis(400),
+            Duration::from_millis(401),
+        );
+        let value: Value = serde_json::from_str(&log.to_json()).unwrap();
+        assert_eq!(value["game_id"], gamestate.game.id.as_str());
+        assert_eq!(value["move"], decision.direction.to_string());
+        assert_eq!(value["directions"][0], "up");
+        assert_eq!(value["reason"], Value::Null);
+        assert_eq!(value["timing"]["deadline_ms"], 400.0);
+        assert!(value["fallback"].as_str().unwrap().contains("deadline"));
+    }
+}
@@ -0,0 +1,16 @@
+use battlesnake_game_of_chicken_lib::regression::{MANIFEST, Manifest};
+use std::env;
+
+#[test]
+fn manifest() {
+    let manifest = Manifest::load(MANIFEST).unwrap();
+    let variants: Vec<String> = env::var("VARIANT").into_iter().collect();
+    let report = manifest.run("requests", &variants, None, |_| {});
+    println!("{}", report);
+    let failures: Vec<String> = report
+        .failures()
+        .iter()
+        .map(|result| format!("{} [{}]: {}", result.file, result.variant, result))
+        .collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,312 @@
+// Differential tests for the rule engines: GameState (general), DGameState (depth_first) and
+// EGameState (legacy) must agree on boards, snakes, health and eliminations for every joint move
+use battlesnake_game_of_chicken_lib::{
+    OriginalBattlesnake, OriginalBoard, OriginalCoord,
+    logic::{
+        depth_first::game::{
+            d_direction::DDirection,
+            d_field::{DFastField, DField},
+            d_game_state::DGameState,
+            d_snake::DSnake,
+        },
+        general::{
+            direction::Direction,
+            field::BasicField,
+            game_state::GameState,
+            moves::Moves,
+            snake::Snake,
+        },
+        legacy::shared::{e_board::EField, e_direction::EDirection, e_game_state::EGameState},
+    },
+};
+use proptest::{
+    array::uniform4, collection::vec, prelude::*, test_runner::FileFailurePersistence,
+};
+
+const SIZE: i8 = 11;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SnakeState {
+    health: u8,
+    length: u8,
+    head: (i8, i8),
+}
+
+/// Engine independent view of a position, rows are printed top down
+#[derive(Debug, PartialEq)]
+struct Snapshot {
+    rows: Vec<String>,
+    snakes: [Option<SnakeState>; 4],
+}
+
+fn snapshot(tile: impl Fn(i8, i8) -> char, snakes: [Option<SnakeState>; 4]) -> Snapshot {
+    let rows = (0..SIZE)
+        .rev()
+        .map(|y| (0..SIZE).map(|x| tile(x, y)).collect())
+        .collect();
+    Snapshot { rows, snakes }
+}
+
+fn snake_char(id: u8) -> char {
+    char::from_digit(id as u32, 10).unwrap()
+}
+
+fn general_snapshot(state: &GameState<BasicField>) -> Snapshot {
+    let snakes = std::array::from_fn(|id| match state.snakes().cell(id as u8).get() {
+        Snake::Alive {
+            health,
+            length,
+            head,
+            ..
+        } => Some(SnakeState {
+            health,
+            length,
+            head: (head.x, head.y),
+        }),
+        _ => None,
+    });
+    snapshot(
+        |x, y| match state.board().cell(x, y).unwrap().get() {
+            BasicField::Empty => '.',
+            BasicField::Food => 'f',
+            BasicField::Snake { id, .. } => snake_char(id),
+        },
+        snakes,
+    )
+}
+
+fn depth_first_snapshot(state: &DGameState<DFastField>) -> Snapshot {
+    let snakes = std::array::from_fn(|id| match state.snakes().cell(id as u8).get() {
+        DSnake::Alive {
+            health,
+            length,
+            head,
+            ..
+        } => Some(SnakeState {
+            health,
+            length,
+            head: (head.x, head.y),
+        }),
+        _ => None,
+    });
+    snapshot(
+        |x, y| {
+            let field = state.board().cell(x, y).unwrap().get();
+            match field.get_type() {
+                DFastField::FOOD => 'f',
+                DFastField::SNAKE => snake_char(field.get_id()),
+                _ => '.',
+            }
+        },
+        snakes,
+    )
+}
+
+fn legacy_snapshot(state: &EGameState) -> Snapshot {
+    let snakes = std::array::from_fn(|id| {
+        state.snakes.get(id as u8).as_ref().map(|snake| SnakeState {
+            health: snake.health,
+            length: snake.length,
+            head: (snake.head.x, snake.head.y),
+        })
+    });
+    snapshot(
+        |x, y| match state.board.get(x, y).unwrap() {
+            EField::Food => 'f',
+            EField::SnakePart { snake_number, .. } => snake_char(snake_number),
+            _ => '.',
+        },
+        snakes,
+    )
+}
+
+#[derive(Debug, Clone)]
+struct SnakeSpec {
+    head: (i8, i8),
+    walk: Vec<u8>,
+    health: i32,
+    stacked: bool,
+}
+
+fn snake_spec() -> impl Strategy<Value = SnakeSpec> {
+    (
+        (0..SIZE, 0..SIZE),
+        vec(0..4u8, 2..12),
+        1..=100i32,
+        prop::bool::weighted(0.2),
+    )
+        .prop_map(|(head, walk, health, stacked)| SnakeSpec {
+            head,
+            walk,
+            health,
+            stacked,
+        })
+}
+
+fn step((x, y): (i8, i8), direction: u8) -> (i8, i8) {
+    match direction {
+        0 => (x, y + 1),
+        1 => (x, y - 1),
+        2 => (x - 1, y),
+        _ => (x + 1, y),
+    }
+}
+
+/// Lays out the snakes as self avoiding walks starting at their heads.
+/// Snakes that are boxed in before reaching length 3 are dropped.
+/// Returns None if our own snake could not be placed.
+fn build_board(specs: &[SnakeSpec], food: &[(i8, i8)]) -> Option<OriginalBoard> {
+    let mut occupied: Vec<(i8, i8)> = Vec::new();
+    let mut snakes = Vec::new();
+    for spec in specs {
+        if occupied.contains(&spec.head) {
+            continue;
+        }
+        let mut body = vec![spec.head];
+        for &direction in &spec.walk {
+            // Blocked steps fall back to the next direction that leads to a free field
+            let free = (0..4)
+                .map(|turn| step(*body.last().unwrap(), (direction + turn) % 4))
+                .find(|next| {
+                    (0..SIZE).contains(&next.0)
+                        && (0..SIZE).contains(&next.1)
+                        && !occupied.contains(next)
+                        && !body.contains(next)
+                });
+            if let Some(next) = free {
+                body.push(next);
+            }
+        }
+        if body.len() < 3 {
+            continue;
+        }
+        occupied.extend(body.iter().copied());
+        if spec.stacked {
+            body.push(*body.last().unwrap());
+        }
+        let body: Vec<OriginalCoord> = body
+            .into_iter()
+            .map(|(x, y)| OriginalCoord {
+                x: x as i32,
+                y: y as i32,
+            })
+            .collect();
+        snakes.push(OriginalBattlesnake {
+            id: format!("snake_{}", snakes.len()),
+            name: format!("snake_{}", snakes.len()),
+            health: spec.health,
+            head: body[0],
+            length: body.len() as i32,
+            body,
+            latency: "0".to_string(),
+            shout: None,
+        });
+    }
+    // Our snake has to come first, the legacy engine only swaps it into slot 0
+    if snakes.first()?.id != "snake_0" {
+        return None;
+    }
+    let mut food: Vec<OriginalCoord> = food
+        .iter()
+        .filter(|coord| !occupied.contains(coord))
+        .map(|&(x, y)| OriginalCoord {
+            x: x as i32,
+            y: y as i32,
+        })
+        .collect();
+    food.dedup();
+    Some(OriginalBoard {
+        height: SIZE as u32,
+        width: SIZE as i32,
+        food,
+        snakes,
+        hazards: Vec::new(),
+    })
+}
+
+fn direction(value: u8) -> Direction {
+    match value {
+        0 => Direction::Up,
+        1 => Direction::Down,
+        2 => Direction::Left,
+        _ => Direction::Right,
+    }
+}
+
+fn d_direction(value: u8) -> DDirection {
+    match value {
+        0 => DDirection::Up,
+        1 => DDirection::Down,
+        2 => DDirection::Left,
+        _ => DDirection::Right,
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: std::env::var("PROPTEST_CASES")
+            .ok()
+            .and_then(|cases| cases.parse().ok())
+            .unwrap_or(2000),
+        failure_persistence: Some(Box::new(FileFailurePersistence::Direct(
+            "tests/rule_engines.proptest-regressions"
+        ))),
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn rule_engines_agree(
+        specs in vec(snake_spec(), 1..=4),
+        food in vec((0..SIZE, 0..SIZE), 0..10),
+        joint_moves in vec(uniform4(0..4u8), 1..12),
+    ) {
+        let board = build_board(&specs, &food);
+        prop_assume!(board.is_some());
+        let board = board.unwrap();
+        let you = board.snakes[0].clone();
+
+        let mut general = GameState::<BasicField>::from_request(&board, &you, &0);
+        let mut depth_first = DGameState::<DFastField>::from_request(&board, &you, &0);
+        let mut legacy = Some(EGameState::from(&board, &you));
+
+        let initial = general_snapshot(&general);
+        prop_assert_eq!(&depth_first_snapshot(&depth_first), &initial);
+        prop_assert_eq!(&legacy_snapshot(legacy.as_ref().unwrap()), &initial);
+
+        for (turn, joint_move) in joint_moves.iter().enumerate() {
+            // Only snakes that are still alive get a move
+            let alive = general_snapshot(&general).snakes.map(|snake| snake.is_some());
+            let moves: Moves =
+                std::array::from_fn(|id| alive[id].then(|| direction(joint_move[id])));
+
+            general.next_state(moves);
+            depth_first.next_state(moves.map(|m| m.map(|d| d_direction(d as u8))));
+            let expected = general_snapshot(&general);
+            prop_assert_eq!(
+                &depth_first_snapshot(&depth_first),
+                &expected,
+                "depth_first diverged in turn {} after {:?}",
+                turn,
+                moves
+            );
+
+            // The legacy engine aborts the simulation when our snake dies
+            if let Some(state) = legacy.as_mut() {
+                let e_moves = moves.map(|m| m.map(|d| EDirection::from_usize(d as usize)));
+                match state.move_snakes(e_moves, u8::MAX, true) {
+                    Ok(()) => prop_assert_eq!(
+                        &legacy_snapshot(state),
+                        &expected,
+                        "legacy diverged in turn {} after {:?}",
+                        turn,
+                        moves
+                    ),
+                    Err(_) => {
+                        prop_assert!(expected.snakes[0].is_none(), "legacy eliminated our snake in turn {}", turn);
+                        legacy = None;
+                    }
+                }
+            }
+        }
+    }
+}